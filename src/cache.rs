@@ -0,0 +1,159 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::Result;
+
+use crate::{
+    client::CloudflareApi,
+    types::{
+        CloudflarePagesCreateProjectRequest, CloudflarePagesDeployment,
+        CloudflarePagesPatchRequest, CloudflarePagesProject, ListPage,
+    },
+};
+
+/// Wraps an API to fetch each project and deployment at most once, for commands that look at the
+/// same project several times, e.g. to check the changes before applying them. Entries are keyed
+/// by account, project and deployment.
+///
+/// Changing a project drops it from the cache, whether or not the request succeeded, so that
+/// retries always see the current state.
+pub struct CachedApi<A> {
+    api: A,
+    projects: RefCell<HashMap<(String, String), CloudflarePagesProject>>,
+    deployments: RefCell<HashMap<(String, String, String), CloudflarePagesDeployment>>,
+}
+
+impl<A> CachedApi<A> {
+    pub fn new(api: A) -> Self {
+        Self {
+            api,
+            projects: Default::default(),
+            deployments: Default::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.api
+    }
+}
+
+impl<A> CachedApi<A>
+where
+    A: CloudflareApi,
+{
+    fn project_key(&self, project: &str) -> (String, String) {
+        (self.api.account().to_owned(), project.to_owned())
+    }
+}
+
+impl<A> CloudflareApi for CachedApi<A>
+where
+    A: CloudflareApi,
+{
+    fn account(&self) -> &str {
+        self.api.account()
+    }
+
+    fn get_project(&self, project: &str) -> Result<CloudflarePagesProject> {
+        let key = self.project_key(project);
+        if let Some(cached) = self.projects.borrow().get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let fetched = self.api.get_project(project)?;
+        self.projects.borrow_mut().insert(key, fetched.clone());
+        Ok(fetched)
+    }
+
+    fn get_deployment(&self, project: &str, deployment: &str) -> Result<CloudflarePagesDeployment> {
+        let key = (
+            self.api.account().to_owned(),
+            project.to_owned(),
+            deployment.to_owned(),
+        );
+        if let Some(cached) = self.deployments.borrow().get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let fetched = self.api.get_deployment(project, deployment)?;
+        self.deployments.borrow_mut().insert(key, fetched.clone());
+        Ok(fetched)
+    }
+
+    fn list_deployments(
+        &self,
+        project: &str,
+        page: usize,
+        per_page: Option<usize>,
+    ) -> Result<ListPage<CloudflarePagesDeployment>> {
+        self.api.list_deployments(project, page, per_page)
+    }
+
+    fn patch_project(
+        &self,
+        project: &str,
+        patch: &CloudflarePagesPatchRequest,
+    ) -> Result<Option<CloudflarePagesProject>> {
+        self.projects
+            .borrow_mut()
+            .remove(&self.project_key(project));
+        self.api.patch_project(project, patch)
+    }
+
+    fn create_project(
+        &self,
+        request: &CloudflarePagesCreateProjectRequest,
+    ) -> Result<CloudflarePagesProject> {
+        self.projects
+            .borrow_mut()
+            .remove(&self.project_key(&request.name));
+        self.api.create_project(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        env_vars::{apply_env_vars, diff_env_vars, ApplyOptions},
+        patch::PatchMode,
+        test_utils::{vars, FakeApi},
+        types::EnvVarsFile,
+    };
+
+    #[test]
+    fn test_fetches_project_once() {
+        let api = CachedApi::new(FakeApi::new("my-project", vars(&[("A", "1")]), vars(&[])));
+
+        api.get_project("my-project").unwrap();
+        api.get_project("my-project").unwrap();
+
+        assert_eq!(api.inner().get_count(), 1);
+        assert!(api
+            .projects
+            .borrow()
+            .contains_key(&("fake-account".to_owned(), "my-project".to_owned())));
+    }
+
+    #[test]
+    fn test_patch_invalidates_project() {
+        let api = CachedApi::new(FakeApi::new("my-project", vars(&[("A", "1")]), vars(&[])));
+        let new_vars = EnvVarsFile {
+            production: Some(vars(&[("A", "2")])),
+            preview: None,
+        };
+
+        diff_env_vars(&api, "my-project", &new_vars, PatchMode::Replace).unwrap();
+        apply_env_vars(
+            &api,
+            "my-project",
+            &new_vars,
+            &ApplyOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+        let diff = diff_env_vars(&api, "my-project", &new_vars, PatchMode::Replace).unwrap();
+
+        assert!(diff.is_empty());
+        assert_eq!(api.inner().get_count(), 2);
+    }
+}
@@ -0,0 +1,410 @@
+use std::fmt::Display;
+
+use anyhow::Result;
+use reqwest::{
+    blocking::{Client, RequestBuilder},
+    StatusCode,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::CfPagesError,
+    types::{
+        CloudflareAccount, CloudflareApiToken, CloudflarePagesCreateProjectRequest,
+        CloudflarePagesDeployment, CloudflarePagesPatchRequest, CloudflarePagesProject,
+        CloudflareResponse, CloudflareTokenVerification, ListPage,
+    },
+};
+
+pub const DEFAULT_API_BASE_URL: &str = "https://api.cloudflare.com/client/v4";
+
+/// Operations against the Cloudflare Pages API used by this crate. Abstracted as a trait so that
+/// logic built on top of it can be exercised against an in-memory fake.
+pub trait CloudflareApi {
+    /// ID of the account operated on. Empty until discovered if not given explicitly.
+    fn account(&self) -> &str;
+
+    fn get_project(&self, project: &str) -> Result<CloudflarePagesProject>;
+
+    fn get_deployment(&self, project: &str, deployment: &str) -> Result<CloudflarePagesDeployment>;
+
+    /// Lists deployments newest first. Pages start at 1, and an empty list is returned past the
+    /// last page. The page size is left to Cloudflare if `per_page` is `None`.
+    fn list_deployments(
+        &self,
+        project: &str,
+        page: usize,
+        per_page: Option<usize>,
+    ) -> Result<ListPage<CloudflarePagesDeployment>>;
+
+    /// Returns the updated project, unless Cloudflare responds without a result.
+    fn patch_project(
+        &self,
+        project: &str,
+        patch: &CloudflarePagesPatchRequest,
+    ) -> Result<Option<CloudflarePagesProject>>;
+
+    fn create_project(
+        &self,
+        request: &CloudflarePagesCreateProjectRequest,
+    ) -> Result<CloudflarePagesProject>;
+}
+
+/// Marker for HTTP 404 responses, turned into more specific errors by the callers.
+#[derive(Debug)]
+struct NotFoundError;
+
+/// A minimal client for the Cloudflare Pages API, scoped to a single account.
+#[derive(Debug, Clone)]
+pub struct CloudflareClient {
+    client: Client,
+    api_base_url: String,
+    account: String,
+    token: String,
+    /// Email of the account owner, for authenticating with a legacy global API key.
+    auth_email: Option<String>,
+}
+
+impl CloudflareClient {
+    pub fn new(client: Client, account: String, token: String) -> Self {
+        Self {
+            client,
+            api_base_url: DEFAULT_API_BASE_URL.to_owned(),
+            account,
+            token,
+            auth_email: None,
+        }
+    }
+
+    /// Authenticates with the `X-Auth-Email` and `X-Auth-Key` headers of legacy API keys instead
+    /// of a bearer token, using the token as the key.
+    pub fn with_legacy_auth(mut self, email: String) -> Self {
+        self.auth_email = Some(email);
+        self
+    }
+
+    /// Sends requests to a different API endpoint, e.g. a mock server or a proxy.
+    pub fn with_api_base_url(mut self, api_base_url: String) -> Self {
+        self.api_base_url = api_base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Picks the account to operate on from the accounts the token has access to, for when the
+    /// account was not given explicitly. Fails unless there is exactly one such account.
+    pub fn with_sole_account(mut self) -> Result<Self> {
+        let accounts: Vec<CloudflareAccount> =
+            self.send(self.client.get(format!("{}/accounts", self.api_base_url)))?;
+
+        self.account = match accounts.as_slice() {
+            [account] => account.id.clone(),
+            [] => anyhow::bail!("the token does not have access to any account"),
+            _ => anyhow::bail!(
+                "the token has access to multiple accounts ({}). Specify one with --account",
+                accounts
+                    .iter()
+                    .map(|account| format!("{} ({})", account.name, account.id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        Ok(self)
+    }
+
+    /// Checks that the token is known to Cloudflare, without requiring any permission.
+    pub fn verify_token(&self) -> Result<CloudflareTokenVerification> {
+        self.send(
+            self.client
+                .get(format!("{}/user/tokens/verify", self.api_base_url)),
+        )
+    }
+
+    /// Looks up the names of the permission groups granted to the token, e.g. `Pages Write`.
+    /// Reading the token details requires the token to be allowed to read API tokens.
+    pub fn token_permissions(&self) -> Result<Vec<String>> {
+        let verification = self.verify_token()?;
+        if verification.status != "active" {
+            anyhow::bail!("the token is {}", verification.status);
+        }
+
+        let token: CloudflareApiToken = self.send(self.client.get(format!(
+            "{}/user/tokens/{}",
+            self.api_base_url, verification.id
+        )))?;
+        Ok(token
+            .policies
+            .into_iter()
+            .filter(|policy| policy.effect == "allow")
+            .flat_map(|policy| policy.permission_groups)
+            .map(|group| group.name)
+            .collect())
+    }
+
+    /// Lists the Pages projects of the account. Pages start at 1, and an empty list is returned
+    /// past the last page.
+    pub fn list_projects(&self, page: usize) -> Result<ListPage<CloudflarePagesProject>> {
+        let response = self.send_for_response(
+            self.client
+                .get(self.projects_url())
+                .query(&[("page", page)]),
+        )?;
+        Ok(ListPage {
+            items: response.result.unwrap_or_default(),
+            total_count: response.result_info.and_then(|info| info.total_count),
+        })
+    }
+
+    fn projects_url(&self) -> String {
+        format!(
+            "{}/accounts/{}/pages/projects",
+            self.api_base_url, self.account
+        )
+    }
+
+    fn project_url(&self, project: &str) -> String {
+        format!("{}/{}", self.projects_url(), project)
+    }
+
+    fn map_project_not_found(&self, err: anyhow::Error, project: &str) -> anyhow::Error {
+        if err.is::<NotFoundError>() {
+            CfPagesError::ProjectNotFound {
+                project: project.to_owned(),
+                account: self.account.clone(),
+            }
+            .into()
+        } else {
+            err
+        }
+    }
+
+    fn send<T>(&self, request: RequestBuilder) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        match self.send_for_response(request)?.result {
+            Some(result) => Ok(result),
+            None => Err(CfPagesError::Api("response has no result".to_owned()).into()),
+        }
+    }
+
+    fn send_for_response<T>(&self, request: RequestBuilder) -> Result<CloudflareResponse<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let request = match &self.auth_email {
+            Some(email) => request
+                .header("X-Auth-Email", email)
+                .header("X-Auth-Key", &self.token),
+            None => request.header("Authorization", format!("Bearer {}", self.token)),
+        };
+        let response = request.send().map_err(CfPagesError::from)?;
+
+        let status = response.status();
+        match status {
+            StatusCode::NOT_FOUND => return Err(NotFoundError.into()),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(CfPagesError::Unauthorized {
+                    status: status.as_u16(),
+                }
+                .into())
+            }
+            StatusCode::TOO_MANY_REQUESTS => return Err(CfPagesError::RateLimited.into()),
+            _ if status.is_server_error() => {
+                return Err(CfPagesError::ServerError {
+                    status: status.as_u16(),
+                }
+                .into())
+            }
+            _ => {}
+        }
+
+        let body = response.text().map_err(CfPagesError::from)?;
+        let response: CloudflareResponse<T> =
+            serde_json::from_str(&body).map_err(CfPagesError::from)?;
+        if !response.success {
+            return Err(
+                CfPagesError::Api(format!("unsuccessful request (HTTP status {status})")).into(),
+            );
+        }
+
+        Ok(response)
+    }
+}
+
+impl Display for NotFoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "resource not found")
+    }
+}
+
+impl std::error::Error for NotFoundError {}
+
+impl CloudflareApi for CloudflareClient {
+    fn account(&self) -> &str {
+        &self.account
+    }
+
+    fn get_project(&self, project: &str) -> Result<CloudflarePagesProject> {
+        self.send(self.client.get(self.project_url(project)))
+            .map_err(|err| self.map_project_not_found(err, project))
+    }
+
+    fn get_deployment(&self, project: &str, deployment: &str) -> Result<CloudflarePagesDeployment> {
+        self.send(self.client.get(format!(
+            "{}/deployments/{}",
+            self.project_url(project),
+            deployment
+        )))
+        .map_err(|err| {
+            if err.is::<NotFoundError>() {
+                CfPagesError::DeploymentNotFound {
+                    project: project.to_owned(),
+                    deployment: deployment.to_owned(),
+                }
+                .into()
+            } else {
+                err
+            }
+        })
+    }
+
+    fn list_deployments(
+        &self,
+        project: &str,
+        page: usize,
+        per_page: Option<usize>,
+    ) -> Result<ListPage<CloudflarePagesDeployment>> {
+        let mut request = self
+            .client
+            .get(format!("{}/deployments", self.project_url(project)))
+            .query(&[("page", page)]);
+        if let Some(per_page) = per_page {
+            request = request.query(&[("per_page", per_page)]);
+        }
+
+        let response = self
+            .send_for_response(request)
+            .map_err(|err| self.map_project_not_found(err, project))?;
+        Ok(ListPage {
+            items: response.result.unwrap_or_default(),
+            total_count: response.result_info.and_then(|info| info.total_count),
+        })
+    }
+
+    fn patch_project(
+        &self,
+        project: &str,
+        patch: &CloudflarePagesPatchRequest,
+    ) -> Result<Option<CloudflarePagesProject>> {
+        Ok(self
+            .send_for_response(self.client.patch(self.project_url(project)).json(patch))
+            .map_err(|err| self.map_project_not_found(err, project))?
+            .result)
+    }
+
+    fn create_project(
+        &self,
+        request: &CloudflarePagesCreateProjectRequest,
+    ) -> Result<CloudflarePagesProject> {
+        self.send(self.client.post(self.projects_url()).json(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// `{"result":{"id":"abc","status":"active"},"success":true}`, gzip-compressed.
+    const GZIP_BODY: &[u8] = &[
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 171, 86, 42, 74, 45, 46, 205, 41, 81, 178, 170, 86, 202,
+        76, 81, 178, 82, 74, 76, 74, 86, 210, 81, 42, 46, 73, 44, 41, 45, 6, 113, 147, 75, 50, 203,
+        82, 149, 106, 129, 66, 165, 201, 201, 169, 197, 64, 177, 146, 162, 210, 212, 90, 0, 161,
+        239, 152, 222, 56, 0, 0, 0,
+    ];
+
+    /// Serves a single response with the given extra headers and body, returning the base URL
+    /// and a handle resolving to the lowercased request.
+    fn serve_once(
+        headers: &'static str,
+        body: &'static [u8],
+    ) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let len = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n{headers}\
+                Content-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+            String::from_utf8_lossy(&request[..len]).to_lowercase()
+        });
+        (format!("http://{address}"), server)
+    }
+
+    #[test]
+    fn test_gzip_response() {
+        let (url, server) = serve_once("Content-Encoding: gzip\r\n", GZIP_BODY);
+
+        let client = CloudflareClient::new(
+            Client::builder().gzip(true).build().unwrap(),
+            "account".to_owned(),
+            "token".to_owned(),
+        )
+        .with_api_base_url(url);
+        let verification = client.verify_token().unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.contains("accept-encoding: gzip"));
+        assert_eq!(verification.status, "active");
+    }
+
+    #[test]
+    fn test_legacy_auth_headers() {
+        let (url, server) = serve_once(
+            "",
+            br#"{"result":{"id":"abc","status":"active"},"success":true}"#,
+        );
+
+        let client = CloudflareClient::new(Client::new(), "account".to_owned(), "key".to_owned())
+            .with_api_base_url(url)
+            .with_legacy_auth("me@example.com".to_owned());
+        client.verify_token().unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.contains("x-auth-email: me@example.com"));
+        assert!(request.contains("x-auth-key: key"));
+        assert!(!request.contains("authorization"));
+    }
+
+    #[test]
+    fn test_null_result() {
+        let client = |url| {
+            CloudflareClient::new(Client::new(), "account".to_owned(), "token".to_owned())
+                .with_api_base_url(url)
+        };
+
+        let patch: CloudflarePagesPatchRequest =
+            serde_json::from_str(r#"{"deployment_configs":{"production":{},"preview":{}}}"#)
+                .unwrap();
+
+        let (url, server) = serve_once("", br#"{"result":null,"success":true}"#);
+        let patched = client(url).patch_project("my-project", &patch).unwrap();
+        server.join().unwrap();
+        assert_eq!(patched, None);
+
+        let (url, server) = serve_once("", br#"{"result":null,"success":true}"#);
+        let err = client(url).get_project("my-project").unwrap_err();
+        server.join().unwrap();
+        assert!(err.to_string().contains("no result"));
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::FullEnvVarsFile;
+
+/// Differences between the production and preview environments of a project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvironmentComparison {
+    pub only_in_production: Vec<String>,
+    pub only_in_preview: Vec<String>,
+    /// Keys present in both environments with different values, or set as a secret in only one
+    /// of them.
+    pub different: Vec<String>,
+    /// Keys that are secrets in both environments. Their values are not returned by the API and
+    /// thus cannot be compared.
+    pub secrets_not_compared: Vec<String>,
+}
+
+impl EnvironmentComparison {
+    pub fn new(vars: &FullEnvVarsFile) -> Self {
+        let mut comparison = Self::default();
+
+        for (key, production_var) in vars.production.iter() {
+            match vars.preview.get(key) {
+                None => comparison.only_in_production.push(key.to_owned()),
+                Some(preview_var) if production_var.secret && preview_var.secret => {
+                    comparison.secrets_not_compared.push(key.to_owned())
+                }
+                Some(preview_var) if preview_var != production_var => {
+                    comparison.different.push(key.to_owned())
+                }
+                Some(_) => {}
+            }
+        }
+        comparison.only_in_preview = vars
+            .preview
+            .keys()
+            .filter(|key| !vars.production.contains_key(*key))
+            .cloned()
+            .collect();
+
+        comparison
+    }
+
+    /// Whether the environments hold the same variables, as far as can be told.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_production.is_empty()
+            && self.only_in_preview.is_empty()
+            && self.different.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::types::EnvVar;
+
+    #[test]
+    fn test_compare_environments() {
+        let vars = FullEnvVarsFile {
+            production: BTreeMap::from([
+                ("SAME".to_owned(), EnvVar::plain("1".to_owned())),
+                ("CHANGED".to_owned(), EnvVar::plain("prod".to_owned())),
+                ("NOW_SECRET".to_owned(), EnvVar::plain("x".to_owned())),
+                ("SECRET".to_owned(), EnvVar::secret("".to_owned())),
+                ("PROD_ONLY".to_owned(), EnvVar::plain("1".to_owned())),
+            ]),
+            preview: BTreeMap::from([
+                ("SAME".to_owned(), EnvVar::plain("1".to_owned())),
+                ("CHANGED".to_owned(), EnvVar::plain("preview".to_owned())),
+                ("NOW_SECRET".to_owned(), EnvVar::secret("".to_owned())),
+                ("SECRET".to_owned(), EnvVar::secret("".to_owned())),
+                ("PREVIEW_ONLY".to_owned(), EnvVar::plain("1".to_owned())),
+            ]),
+        };
+
+        let comparison = EnvironmentComparison::new(&vars);
+
+        assert_eq!(comparison.only_in_production, vec!["PROD_ONLY".to_owned()]);
+        assert_eq!(comparison.only_in_preview, vec!["PREVIEW_ONLY".to_owned()]);
+        assert_eq!(
+            comparison.different,
+            vec!["CHANGED".to_owned(), "NOW_SECRET".to_owned()]
+        );
+        assert_eq!(comparison.secrets_not_compared, vec!["SECRET".to_owned()]);
+        assert!(!comparison.is_empty());
+    }
+}
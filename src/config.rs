@@ -0,0 +1,250 @@
+use std::{
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::error::CfPagesError;
+
+/// Name of the directory holding the config file inside the platform config directory.
+const APP_DIR_NAME: &str = "cf-pages-cli";
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Settings read from the TOML config file. All fields are optional, and values given on the
+/// command line or in the environment take precedence.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// Fields of the config file that can be set from the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConfigKey {
+    Account,
+    Token,
+}
+
+impl ConfigKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Account => "account",
+            Self::Token => "token",
+        }
+    }
+}
+
+/// Sets a field in the TOML content of a config file, keeping all other fields.
+pub fn set_config_value(content: &str, key: ConfigKey, value: &str) -> Result<String> {
+    let mut table: Table = toml::from_str(content)?;
+    table.insert(key.as_str().to_owned(), Value::String(value.to_owned()));
+    Ok(toml::to_string_pretty(&table)?)
+}
+
+/// Renders the TOML content of a config file for display, masking all but the last 4 characters
+/// of the token.
+pub fn show_config(content: &str) -> Result<String> {
+    let mut table: Table = toml::from_str(content)?;
+    if let Some(Value::String(token)) = table.get_mut(ConfigKey::Token.as_str()) {
+        *token = mask_secret(token);
+    }
+    Ok(toml::to_string_pretty(&table)?)
+}
+
+fn mask_secret(secret: &str) -> String {
+    let chars = secret.chars().collect::<Vec<_>>();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let visible = chars[chars.len() - 4..].iter().collect::<String>();
+    format!("{}{}", "*".repeat(chars.len() - 4), visible)
+}
+
+/// Writes a file by first writing a temporary file next to it and then renaming it into place,
+/// so that an interrupted write never leaves a truncated file behind. Missing parent directories
+/// are created. On Unix, new files are only readable by their owner, as they may hold the token,
+/// while replaced files keep their permissions.
+pub fn write_file_atomically(path: &Path, content: &str) -> Result<()> {
+    Ok(write_atomically(path, content).map_err(CfPagesError::Io)?)
+}
+
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = std::fs::File::create(&temp_path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = match std::fs::metadata(path) {
+            Ok(metadata) => metadata.permissions().mode(),
+            Err(_) => crate::output::SECRET_FILE_MODE,
+        };
+        // Applied before writing anything, as the file is created with the default umask
+        file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+    }
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Resolves the default location of the config file for the current platform:
+///
+/// - Linux and other Unix systems: `$XDG_CONFIG_HOME/cf-pages-cli/config.toml`, falling back to
+///   `~/.config` when `XDG_CONFIG_HOME` is unset or not absolute.
+/// - macOS: `~/Library/Application Support/cf-pages-cli/config.toml`.
+/// - Windows: `%APPDATA%\cf-pages-cli\config.toml`.
+///
+/// Returns `None` if the relevant environment variables are not set.
+pub fn default_config_path() -> Option<PathBuf> {
+    config_path_for(std::env::consts::OS, |name| std::env::var_os(name))
+}
+
+fn config_path_for<F>(os: &str, var: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let non_empty = |name: &str| {
+        var(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let config_dir = match os {
+        "windows" => non_empty("APPDATA")?,
+        "macos" => non_empty("HOME")?
+            .join("Library")
+            .join("Application Support"),
+        _ => match non_empty("XDG_CONFIG_HOME") {
+            // The spec requires relative paths to be ignored
+            Some(dir) if dir.is_absolute() => dir,
+            _ => non_empty("HOME")?.join(".config"),
+        },
+    };
+
+    Some(config_dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_set_config_value() {
+        let content = "account = \"old\"\nextra = 1\n";
+
+        let content = set_config_value(content, ConfigKey::Account, "new").unwrap();
+        let content = set_config_value(&content, ConfigKey::Token, "secret").unwrap();
+
+        let config: Config = toml::from_str(&content).unwrap();
+        assert_eq!(config.account.as_deref(), Some("new"));
+        assert_eq!(config.token.as_deref(), Some("secret"));
+        assert!(content.contains("extra = 1"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_atomically_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join("cf-pages-test-config-permissions.toml");
+        let _ = std::fs::remove_file(&path);
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        write_file_atomically(&path, "token = \"a\"\n").unwrap();
+        assert_eq!(mode(&path), 0o600);
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        write_file_atomically(&path, "token = \"b\"\n").unwrap();
+        assert_eq!(mode(&path), 0o640);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_show_config() {
+        assert_eq!(
+            show_config("account = \"abc\"\ntoken = \"0123456789abcdef\"\n").unwrap(),
+            "account = \"abc\"\ntoken = \"************cdef\"\n"
+        );
+        assert_eq!(
+            show_config("token = \"short\"").unwrap(),
+            "token = \"*****\"\n"
+        );
+    }
+
+    fn resolve(os: &str, vars: &[(&str, &str)]) -> Option<PathBuf> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        config_path_for(os, |name| vars.get(name).map(OsString::from))
+    }
+
+    #[test]
+    fn test_config_path_for_linux() {
+        assert_eq!(
+            resolve(
+                "linux",
+                &[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/me")]
+            ),
+            Some(PathBuf::from("/xdg/cf-pages-cli/config.toml"))
+        );
+        assert_eq!(
+            resolve(
+                "linux",
+                &[("XDG_CONFIG_HOME", "relative"), ("HOME", "/home/me")]
+            ),
+            Some(PathBuf::from("/home/me/.config/cf-pages-cli/config.toml"))
+        );
+        assert_eq!(
+            resolve("linux", &[("XDG_CONFIG_HOME", ""), ("HOME", "/home/me")]),
+            Some(PathBuf::from("/home/me/.config/cf-pages-cli/config.toml"))
+        );
+        assert_eq!(resolve("linux", &[]), None);
+    }
+
+    #[test]
+    fn test_config_path_for_macos() {
+        assert_eq!(
+            resolve(
+                "macos",
+                &[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/Users/me")]
+            ),
+            Some(PathBuf::from(
+                "/Users/me/Library/Application Support/cf-pages-cli/config.toml"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_config_path_for_windows() {
+        assert_eq!(
+            resolve("windows", &[("APPDATA", "C:\\Users\\me\\AppData\\Roaming")]),
+            Some(
+                PathBuf::from("C:\\Users\\me\\AppData\\Roaming")
+                    .join("cf-pages-cli")
+                    .join("config.toml")
+            )
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::Result;
+
+use crate::{
+    client::CloudflareApi,
+    time::parse_rfc3339,
+    types::{CloudflarePagesDeployment, Environment, ListPage},
+};
+
+/// Criteria for [`list_deployments`].
+#[derive(Debug, Clone, Default)]
+pub struct DeploymentFilter {
+    /// Only include deployments created at or after this Unix timestamp in seconds. Only the
+    /// first page of deployments is fetched if `None`.
+    pub created_after: Option<i64>,
+    pub environment: Option<Environment>,
+    /// Stops after this many matching deployments, fetching further pages if needed.
+    pub limit: Option<usize>,
+    /// Number of deployments to fetch per request. Left to Cloudflare if `None`.
+    pub per_page: Option<usize>,
+    /// Page to start listing from, for resuming an earlier listing. Starts at 1 if `None`.
+    pub start_page: Option<usize>,
+}
+
+/// Lists the deployments of a project matching `filter`, newest first.
+pub fn list_deployments<A>(
+    api: &A,
+    project: &str,
+    filter: &DeploymentFilter,
+) -> Result<Vec<CloudflarePagesDeployment>>
+where
+    A: CloudflareApi,
+{
+    let mut deployments = vec![];
+    for_each_deployment(api, project, filter, |deployment| {
+        deployments.push(deployment);
+        Ok(())
+    })?;
+    Ok(deployments)
+}
+
+/// Like [`list_deployments`], but hands each deployment to `f` as soon as its page is fetched
+/// instead of collecting them. Returns the total number of deployments of the project, regardless
+/// of the filter, if reported by Cloudflare.
+pub fn for_each_deployment<A, F>(
+    api: &A,
+    project: &str,
+    filter: &DeploymentFilter,
+    mut f: F,
+) -> Result<Option<usize>>
+where
+    A: CloudflareApi,
+    F: FnMut(CloudflarePagesDeployment) -> Result<()>,
+{
+    // Cloudflare returns deployments newest first, so paging stops at the first one that is too
+    // old
+    let mut page = filter.start_page.unwrap_or(1);
+    let mut total_count = None;
+    let mut matched = 0;
+    'pages: loop {
+        let ListPage {
+            items,
+            total_count: page_total_count,
+        } = api.list_deployments(project, page, filter.per_page)?;
+        total_count = total_count.or(page_total_count);
+        if items.is_empty() {
+            break;
+        }
+
+        for deployment in items.into_iter() {
+            if filter.limit.is_some_and(|limit| matched >= limit) {
+                break 'pages;
+            }
+            if let Some(created_after) = filter.created_after {
+                if parse_rfc3339(&deployment.created_on)? < created_after {
+                    break 'pages;
+                }
+            }
+            if filter
+                .environment
+                .is_none_or(|environment| environment == deployment.environment)
+            {
+                matched += 1;
+                f(deployment)?;
+            }
+        }
+
+        if filter.created_after.is_none() && filter.limit.is_none() {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(total_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::FakeApi;
+
+    fn fake_api() -> FakeApi {
+        let mut api = FakeApi::new("my-project", Default::default(), Default::default());
+        for day in 1..=30 {
+            let environment = if day % 2 == 0 {
+                Environment::Production
+            } else {
+                Environment::Preview
+            };
+            api = api.with_deployment(
+                &format!("deployment-{day}"),
+                environment,
+                &format!("2023-04-{day:02}T12:00:00Z"),
+                Default::default(),
+            );
+        }
+        api
+    }
+
+    #[test]
+    fn test_list_deployments_first_page() {
+        let deployments =
+            list_deployments(&fake_api(), "my-project", &DeploymentFilter::default()).unwrap();
+
+        assert_eq!(deployments.len(), 25);
+        assert_eq!(deployments[0].id, "deployment-30");
+    }
+
+    #[test]
+    fn test_list_deployments_limit() {
+        let mut ids = vec![];
+        let total_count = for_each_deployment(
+            &fake_api(),
+            "my-project",
+            &DeploymentFilter {
+                limit: Some(4),
+                per_page: Some(3),
+                ..Default::default()
+            },
+            |deployment| {
+                ids.push(deployment.id);
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(total_count, Some(30));
+        assert_eq!(
+            ids,
+            vec![
+                "deployment-30",
+                "deployment-29",
+                "deployment-28",
+                "deployment-27"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_deployments_start_page() {
+        let deployments = list_deployments(
+            &fake_api(),
+            "my-project",
+            &DeploymentFilter {
+                limit: Some(2),
+                per_page: Some(3),
+                start_page: Some(2),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            deployments
+                .iter()
+                .map(|deployment| deployment.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["deployment-27", "deployment-26"]
+        );
+    }
+
+    #[test]
+    fn test_for_each_deployment_stops_on_error() {
+        let mut seen = vec![];
+        let result = for_each_deployment(
+            &fake_api(),
+            "my-project",
+            &DeploymentFilter::default(),
+            |deployment| {
+                if seen.len() == 2 {
+                    anyhow::bail!("stop");
+                }
+                seen.push(deployment.id);
+                Ok(())
+            },
+        );
+
+        assert!(result.is_err());
+        assert_eq!(seen, vec!["deployment-30", "deployment-29"]);
+    }
+
+    #[test]
+    fn test_list_deployments_since() {
+        let deployments = list_deployments(
+            &fake_api(),
+            "my-project",
+            &DeploymentFilter {
+                created_after: Some(parse_rfc3339("2023-04-03T00:00:00Z").unwrap()),
+                environment: Some(Environment::Production),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            deployments
+                .iter()
+                .map(|deployment| deployment.id.as_str())
+                .collect::<Vec<_>>(),
+            (2..=15)
+                .rev()
+                .map(|half_day| format!("deployment-{}", half_day * 2))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{
+    patch::{generate_deployment_configs_patch, PatchEntryFilter, PatchMode},
+    summary::ChangeSummary,
+    types::{CloudflarePagesDeploymentConfigs, EnvVar, EnvVarsFile, Environment, FullEnvVarsFile},
+};
+
+/// Differences between the existing variables of a project and the desired ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVarsDiff {
+    pub old_vars: FullEnvVarsFile,
+    pub patch: CloudflarePagesDeploymentConfigs,
+}
+
+impl EnvVarsDiff {
+    pub fn new(old_vars: FullEnvVarsFile, new_vars: &EnvVarsFile, mode: PatchMode) -> Self {
+        let patch = generate_deployment_configs_patch(&old_vars, new_vars, mode);
+        Self { old_vars, patch }
+    }
+
+    pub fn summary(&self) -> ChangeSummary {
+        ChangeSummary::from_patch(&self.old_vars, &self.patch)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patch.is_empty()
+    }
+
+    /// Drops the given keys from the patch, so that they are neither added, changed nor deleted,
+    /// whatever the desired variables say.
+    pub fn ignoring_keys(mut self, keys: &[String]) -> Self {
+        for env in [&mut self.patch.production, &mut self.patch.preview] {
+            if let Some(changes) = env.env_vars.as_mut() {
+                changes.retain(|key, _| !keys.contains(key));
+            }
+        }
+        self
+    }
+
+    /// Keeps only the additions (including changes) or only the deletions of the patch.
+    pub fn only(mut self, filter: PatchEntryFilter) -> Self {
+        for env in [&mut self.patch.production, &mut self.patch.preview] {
+            if let Some(changes) = env.env_vars.as_mut() {
+                changes.retain(|_, value| match filter {
+                    PatchEntryFilter::Additions => value.is_some(),
+                    PatchEntryFilter::Deletions => value.is_none(),
+                });
+            }
+        }
+        self
+    }
+
+    /// Renders the changes as a unified diff of `KEY=VALUE` lines, with one section per
+    /// environment that has changes. Returns an empty string if there are no changes.
+    pub fn to_unified(&self) -> String {
+        let mut buffer = String::new();
+
+        for (environment, old_env, patch_env) in [
+            (
+                Environment::Production,
+                &self.old_vars.production,
+                &self.patch.production,
+            ),
+            (
+                Environment::Preview,
+                &self.old_vars.preview,
+                &self.patch.preview,
+            ),
+        ] {
+            let changes = match patch_env.env_vars.as_ref() {
+                Some(changes) if !changes.is_empty() => changes,
+                _ => continue,
+            };
+
+            buffer.push_str(&format!("--- {environment} (remote)\n"));
+            buffer.push_str(&format!("+++ {environment} (local)\n"));
+            for (key, new_value) in changes.iter() {
+                if let Some(old_value) = old_env.get(key) {
+                    buffer.push_str(&format!("-{}\n", render_line(key, old_value)));
+                }
+                if let Some(new_value) = new_value {
+                    buffer.push_str(&format!("+{}\n", render_line(key, &new_value.into())));
+                }
+            }
+        }
+
+        buffer
+    }
+
+    /// Renders the changes per key with their values: `+ KEY=value` for additions,
+    /// `~ KEY: old -> new` for changes and `- KEY` for deletions, under a header for each
+    /// environment that has changes. Returns an empty string if there are no changes.
+    pub fn to_value_changes(&self) -> String {
+        let mut buffer = String::new();
+
+        for (environment, old_env, patch_env) in [
+            (
+                Environment::Production,
+                &self.old_vars.production,
+                &self.patch.production,
+            ),
+            (
+                Environment::Preview,
+                &self.old_vars.preview,
+                &self.patch.preview,
+            ),
+        ] {
+            let changes = match patch_env.env_vars.as_ref() {
+                Some(changes) if !changes.is_empty() => changes,
+                _ => continue,
+            };
+
+            buffer.push_str(&format!("{environment}:\n"));
+            for (key, new_value) in changes.iter() {
+                let line = match (old_env.get(key), new_value) {
+                    (None, Some(new_value)) => {
+                        format!("  + {}", render_line(key, &new_value.into()))
+                    }
+                    (Some(old_value), Some(new_value)) => format!(
+                        "  ~ {key}: {} -> {}",
+                        render_value(old_value),
+                        render_value(&new_value.into())
+                    ),
+                    (_, None) => format!("  - {key}"),
+                };
+                buffer.push_str(&line);
+                buffer.push('\n');
+            }
+        }
+
+        buffer
+    }
+}
+
+fn render_value(var: &EnvVar) -> String {
+    if var.secret {
+        format!("{} (secret)", var.value)
+    } else {
+        var.value.to_owned()
+    }
+}
+
+fn render_line(key: &str, var: &EnvVar) -> String {
+    if var.secret {
+        format!("{}={} (secret)", key, var.value)
+    } else {
+        format!("{}={}", key, var.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::vars;
+
+    #[test]
+    fn test_to_unified() {
+        let diff = EnvVarsDiff::new(
+            FullEnvVarsFile {
+                production: vars(&[("KEEP", "1"), ("CHANGE", "old"), ("REMOVE", "x")]),
+                preview: vars(&[("A", "1")]),
+            },
+            &EnvVarsFile {
+                production: Some(vars(&[("KEEP", "1"), ("CHANGE", "new"), ("ADD", "2")])),
+                preview: Some(vars(&[("A", "1")])),
+            },
+            PatchMode::Replace,
+        );
+
+        assert_eq!(
+            diff.to_unified(),
+            "--- production (remote)\n\
+             +++ production (local)\n\
+             +ADD=2\n\
+             -CHANGE=old\n\
+             +CHANGE=new\n\
+             -REMOVE=x\n"
+        );
+        assert_eq!(diff.summary().production.added, vec!["ADD".to_owned()]);
+    }
+
+    #[test]
+    fn test_to_value_changes() {
+        let diff = EnvVarsDiff::new(
+            FullEnvVarsFile {
+                production: vars(&[("KEEP", "1"), ("CHANGE", "old"), ("REMOVE", "x")]),
+                preview: vars(&[("A", "1")]),
+            },
+            &EnvVarsFile {
+                production: Some(vars(&[("KEEP", "1"), ("CHANGE", "new"), ("ADD", "2")])),
+                preview: Some(vars(&[("A", "1")])),
+            },
+            PatchMode::Replace,
+        );
+
+        assert_eq!(
+            diff.to_value_changes(),
+            "production:\n  + ADD=2\n  ~ CHANGE: old -> new\n  - REMOVE\n"
+        );
+    }
+
+    #[test]
+    fn test_ignoring_keys() {
+        let diff = EnvVarsDiff::new(
+            FullEnvVarsFile {
+                production: vars(&[("ROTATED", "secret"), ("OLD", "1")]),
+                preview: vars(&[]),
+            },
+            &EnvVarsFile {
+                production: Some(vars(&[("NEW", "2")])),
+                preview: Some(vars(&[("ROTATED", "stale")])),
+            },
+            PatchMode::Replace,
+        )
+        .ignoring_keys(&["ROTATED".to_owned()]);
+
+        let summary = diff.summary();
+        assert_eq!(summary.production.added, vec!["NEW".to_owned()]);
+        assert_eq!(summary.production.removed, vec!["OLD".to_owned()]);
+        assert!(summary.preview.is_empty());
+    }
+
+    #[test]
+    fn test_only() {
+        let diff = EnvVarsDiff::new(
+            FullEnvVarsFile {
+                production: vars(&[("CHANGE", "old"), ("REMOVE", "x")]),
+                preview: vars(&[]),
+            },
+            &EnvVarsFile {
+                production: Some(vars(&[("CHANGE", "new"), ("ADD", "2")])),
+                preview: None,
+            },
+            PatchMode::Replace,
+        );
+
+        let additions = diff.clone().only(PatchEntryFilter::Additions).summary();
+        assert_eq!(additions.production.added, vec!["ADD".to_owned()]);
+        assert_eq!(additions.production.changed, vec!["CHANGE".to_owned()]);
+        assert!(additions.production.removed.is_empty());
+
+        let deletions = diff.only(PatchEntryFilter::Deletions).summary();
+        assert!(deletions.production.added.is_empty());
+        assert!(deletions.production.changed.is_empty());
+        assert_eq!(deletions.production.removed, vec!["REMOVE".to_owned()]);
+    }
+
+    #[test]
+    fn test_to_unified_no_changes() {
+        let diff = EnvVarsDiff::new(
+            FullEnvVarsFile {
+                production: vars(&[("A", "1")]),
+                preview: vars(&[]),
+            },
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1")])),
+                preview: None,
+            },
+            PatchMode::Replace,
+        );
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_unified(), "");
+        assert_eq!(diff.to_value_changes(), "");
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+/// Quoting policy for values written to `.env` files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EnvFileQuote {
+    /// Wraps every value in double quotes, escaping as needed.
+    Always,
+    /// Writes values as-is. Line breaks are removed since they cannot be represented.
+    Never,
+    /// Only quotes values that would otherwise be misread by dotenv parsers.
+    Auto,
+}
+
+impl EnvFileQuote {
+    /// Renders a single `KEY=VALUE` line, without the trailing newline.
+    pub fn format_line(&self, key: &str, value: &str) -> String {
+        let quote = match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => needs_quotes(value),
+        };
+
+        if quote {
+            format!("{}=\"{}\"", key, escape(value))
+        } else {
+            format!("{}={}", key, value.replace("\r\n", "").replace('\n', ""))
+        }
+    }
+}
+
+/// Renders an `export KEY='VALUE'` line for sourcing from POSIX shells, without the trailing
+/// newline. Values are always single-quoted, so `$`, backticks and backslashes are taken
+/// literally, with single quotes written as `'\''`.
+pub fn format_shell_line(key: &str, value: &str) -> String {
+    format!("export {}='{}'", key, value.replace('\'', r"'\''"))
+}
+
+/// Renders a `KEY=VALUE` line for a systemd `EnvironmentFile`, without the trailing newline.
+/// systemd never expands variables, so `$` and backticks are written as-is. Values with
+/// whitespace, quotes or backslashes are double-quoted, escaping `"` and `\`, with line breaks
+/// kept literally as systemd allows within quotes.
+pub fn format_systemd_line(key: &str, value: &str) -> String {
+    if value
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'))
+    {
+        format!(
+            "{}=\"{}\"",
+            key,
+            value.replace('\\', r"\\").replace('"', r#"\""#)
+        )
+    } else {
+        format!("{key}={value}")
+    }
+}
+
+/// Renders a `key=value` line of a Java `.properties` file, without the trailing newline. Escapes
+/// follow `java.util.Properties.store`: `\`, `=`, `:`, `#`, `!` and control characters are
+/// backslash-escaped, as are spaces in the key and a leading space in the value, and characters
+/// outside printable ASCII are written as `\uXXXX` UTF-16 escapes.
+pub fn format_properties_line(key: &str, value: &str) -> String {
+    format!(
+        "{}={}",
+        escape_properties(key, true),
+        escape_properties(value, false)
+    )
+}
+
+fn escape_properties(text: &str, is_key: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (ind, c) in text.chars().enumerate() {
+        match c {
+            ' ' if is_key || ind == 0 => escaped.push_str("\\ "),
+            '\\' | '=' | ':' | '#' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{c}' => escaped.push_str("\\f"),
+            ' '..='~' => escaped.push(c),
+            _ => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\u{unit:04X}"));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+/// Renders the `SHELL-FORMAT` argument of `envsubst` listing the given keys, e.g. `${A} ${B}`, so
+/// that only these variables are substituted in a template.
+pub fn format_envsubst_vars<'a, I>(keys: I) -> String
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    keys.into_iter()
+        .map(|key| format!("${{{key}}}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Line separator used when writing `.env` files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// `crlf` on Windows and `lf` everywhere else.
+    Native,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+            Self::Native => {
+                if cfg!(windows) {
+                    "\r\n"
+                } else {
+                    "\n"
+                }
+            }
+        }
+    }
+}
+
+/// A variable assignment parsed from a dotenv file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotenvEntry {
+    /// 1-based line number of the assignment.
+    pub line: usize,
+    pub key: String,
+    pub value: String,
+    /// Text of the comment lines directly above the assignment, without the leading `#`.
+    pub comment: Option<String>,
+}
+
+/// Parses dotenv content into its assignments, in file order. Supports blank lines, `#` comments,
+/// an optional `export` prefix, and unquoted, single-quoted (literal) or double-quoted (with `\n`,
+/// `\r`, `\"` and `\\` escapes) values. Duplicate keys are kept as separate entries. A block of
+/// comment lines is attached to the assignment right below it, unless separated by a blank line.
+pub fn parse_dotenv(content: &str) -> Result<Vec<DotenvEntry>> {
+    let mut entries = vec![];
+    let mut comment_lines: Vec<&str> = vec![];
+
+    for (ind, raw_line) in content.lines().enumerate() {
+        let line_number = ind + 1;
+        let line = raw_line.trim();
+        if line.is_empty() {
+            comment_lines.clear();
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            comment_lines.push(comment.strip_prefix(' ').unwrap_or(comment));
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => anyhow::bail!("line {line_number}: expected KEY=VALUE"),
+        };
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            anyhow::bail!("line {line_number}: invalid key");
+        }
+
+        entries.push(DotenvEntry {
+            line: line_number,
+            key: key.to_owned(),
+            value: parse_value(value.trim_start())
+                .map_err(|err| anyhow::anyhow!("line {line_number}: {err}"))?,
+            comment: if comment_lines.is_empty() {
+                None
+            } else {
+                Some(comment_lines.join("\n"))
+            },
+        });
+        comment_lines.clear();
+    }
+
+    Ok(entries)
+}
+
+/// Finds keys assigned more than once, along with the line numbers of all their assignments.
+pub fn find_duplicate_keys(entries: &[DotenvEntry]) -> Vec<(String, Vec<usize>)> {
+    let mut lines_by_key: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for entry in entries.iter() {
+        lines_by_key.entry(&entry.key).or_default().push(entry.line);
+    }
+
+    lines_by_key
+        .into_iter()
+        .filter(|(_, lines)| lines.len() > 1)
+        .map(|(key, lines)| (key.to_owned(), lines))
+        .collect()
+}
+
+/// Collects the comments of each key, for storing alongside the variables. Like values, the
+/// comment of the last assignment of a key wins.
+pub fn collect_comments(entries: &[DotenvEntry]) -> BTreeMap<String, String> {
+    let mut comments = BTreeMap::new();
+    for entry in entries.iter() {
+        match &entry.comment {
+            Some(comment) => comments.insert(entry.key.clone(), comment.clone()),
+            None => comments.remove(&entry.key),
+        };
+    }
+    comments
+}
+
+/// Renders a comment as `#` lines, each followed by `line_ending`.
+pub fn format_comment(comment: &str, line_ending: LineEnding) -> String {
+    comment
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                format!("#{}", line_ending.as_str())
+            } else {
+                format!("# {line}{}", line_ending.as_str())
+            }
+        })
+        .collect()
+}
+
+fn parse_value(raw: &str) -> Result<String> {
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => anyhow::bail!("unterminated double-quoted value"),
+                },
+                Some(c) => value.push(c),
+                None => anyhow::bail!("unterminated double-quoted value"),
+            }
+        }
+        Ok(value)
+    } else if let Some(rest) = raw.strip_prefix('\'') {
+        match rest.find('\'') {
+            Some(end) => Ok(rest[..end].to_owned()),
+            None => anyhow::bail!("unterminated single-quoted value"),
+        }
+    } else {
+        // Inline comments need to be preceded by whitespace, so that `#` can appear in values
+        let value = match raw.find(" #") {
+            Some(start) => &raw[..start],
+            None => raw,
+        };
+        Ok(value.trim_end().to_owned())
+    }
+}
+
+fn needs_quotes(value: &str) -> bool {
+    value
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '#' | '"' | '\'' | '\\' | '$' | '`'))
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_always() {
+        assert_eq!(
+            EnvFileQuote::Always.format_line("A", "plain"),
+            "A=\"plain\""
+        );
+        assert_eq!(
+            EnvFileQuote::Always.format_line("A", "with space"),
+            "A=\"with space\""
+        );
+        assert_eq!(
+            EnvFileQuote::Always.format_line("A", "say \"hi\"\\n\nbye"),
+            "A=\"say \\\"hi\\\"\\\\n\\nbye\""
+        );
+    }
+
+    #[test]
+    fn test_never() {
+        assert_eq!(EnvFileQuote::Never.format_line("A", "plain"), "A=plain");
+        assert_eq!(
+            EnvFileQuote::Never.format_line("A", "with space"),
+            "A=with space"
+        );
+        assert_eq!(
+            EnvFileQuote::Never.format_line("A", "multi\r\nline\n"),
+            "A=multiline"
+        );
+    }
+
+    #[test]
+    fn test_auto() {
+        assert_eq!(EnvFileQuote::Auto.format_line("A", "plain"), "A=plain");
+        assert_eq!(EnvFileQuote::Auto.format_line("A", ""), "A=");
+        assert_eq!(
+            EnvFileQuote::Auto.format_line("A", "with space"),
+            "A=\"with space\""
+        );
+        assert_eq!(
+            EnvFileQuote::Auto.format_line("A", "multi\nline"),
+            "A=\"multi\\nline\""
+        );
+        assert_eq!(EnvFileQuote::Auto.format_line("A", "a#b"), "A=\"a#b\"");
+    }
+
+    #[test]
+    fn test_format_shell_line() {
+        assert_eq!(format_shell_line("A", "plain"), "export A='plain'");
+        assert_eq!(format_shell_line("A", ""), "export A=''");
+        assert_eq!(
+            format_shell_line("A", r"it's $HOME `pwd` \n"),
+            r"export A='it'\''s $HOME `pwd` \n'"
+        );
+        assert_eq!(format_shell_line("A", "''"), r"export A=''\'''\'''");
+    }
+
+    #[test]
+    fn test_format_systemd_line() {
+        assert_eq!(format_systemd_line("A", "plain"), "A=plain");
+        assert_eq!(format_systemd_line("A", ""), "A=");
+        assert_eq!(format_systemd_line("A", "$HOME#`x`"), "A=$HOME#`x`");
+        assert_eq!(
+            format_systemd_line("A", " say \"hi\" \\ "),
+            r#"A=" say \"hi\" \\ ""#
+        );
+        assert_eq!(format_systemd_line("A", "multi\nline"), "A=\"multi\nline\"");
+    }
+
+    #[test]
+    fn test_format_properties_line() {
+        assert_eq!(format_properties_line("a.b", "plain"), "a.b=plain");
+        assert_eq!(
+            format_properties_line("my key:x", " a=b #c"),
+            r"my\ key\:x=\ a\=b \#c"
+        );
+        assert_eq!(
+            format_properties_line("A", "tab\there\nC:\\dir"),
+            r"A=tab\there\nC\:\\dir"
+        );
+        assert_eq!(
+            format_properties_line("A", "caf\u{e9} \u{1f600}"),
+            r"A=caf\u00E9 \uD83D\uDE00"
+        );
+    }
+
+    #[test]
+    fn test_format_envsubst_vars() {
+        let keys = ["API_URL".to_owned(), "PORT".to_owned()];
+
+        assert_eq!(format_envsubst_vars(&keys), "${API_URL} ${PORT}");
+        assert_eq!(format_envsubst_vars(&[]), "");
+    }
+
+    #[test]
+    fn test_line_ending_does_not_affect_escaped_values() {
+        let line = EnvFileQuote::Auto.format_line("A", "multi\r\nline");
+        let content = [line.as_str(), "B=1", ""].join(LineEnding::Crlf.as_str());
+
+        assert_eq!(content, "A=\"multi\\r\\nline\"\r\nB=1\r\n");
+    }
+
+    #[test]
+    fn test_parse_dotenv() {
+        let content = r#"
+# comment
+export A=1
+B = plain value # trailing comment
+C="quoted \"value\"\nwith escapes"
+D='single $quoted'
+E=
+F=a#b
+"#;
+
+        let entries = parse_dotenv(content).unwrap();
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.line, entry.key.as_str(), entry.value.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, "A", "1"),
+                (4, "B", "plain value"),
+                (5, "C", "quoted \"value\"\nwith escapes"),
+                (6, "D", "single $quoted"),
+                (7, "E", ""),
+                (8, "F", "a#b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_dotenv_round_trip() {
+        let value = "multi\nline \"value\" with \\ backslash";
+        let line = EnvFileQuote::Always.format_line("A", value);
+
+        let entries = parse_dotenv(&line).unwrap();
+
+        assert_eq!(entries[0].value, value);
+    }
+
+    #[test]
+    fn test_find_duplicate_keys() {
+        let entries = parse_dotenv("A=1\nB=2\n\nA=3\nC=4\nA=5\nC=6").unwrap();
+
+        assert_eq!(
+            find_duplicate_keys(&entries),
+            vec![
+                ("A".to_owned(), vec![1, 4, 6]),
+                ("C".to_owned(), vec![5, 7]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_dotenv_comments() {
+        let content =
+            "# detached\n\n# API endpoint\n#\n#  indented\nA=1\nB=2\n# first\nC=3\n# second\nC=4";
+
+        let entries = parse_dotenv(content).unwrap();
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.comment.as_deref())
+                .collect::<Vec<_>>(),
+            vec![
+                Some("API endpoint\n\n indented"),
+                None,
+                Some("first"),
+                Some("second")
+            ]
+        );
+        assert_eq!(
+            collect_comments(&entries),
+            BTreeMap::from([
+                ("A".to_owned(), "API endpoint\n\n indented".to_owned()),
+                ("C".to_owned(), "second".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_comment_round_trip() {
+        let comment = "API endpoint\n\n indented";
+        let content = format!(
+            "{}{}",
+            format_comment(comment, LineEnding::Lf),
+            EnvFileQuote::Auto.format_line("A", "1")
+        );
+
+        let entries = parse_dotenv(&content).unwrap();
+
+        assert_eq!(entries[0].comment.as_deref(), Some(comment));
+    }
+
+    #[test]
+    fn test_parse_dotenv_invalid() {
+        assert!(parse_dotenv("NO_EQUALS_SIGN").is_err());
+        assert!(parse_dotenv("A=\"unterminated").is_err());
+        assert!(parse_dotenv("A='unterminated").is_err());
+        assert!(parse_dotenv("=value").is_err());
+    }
+}
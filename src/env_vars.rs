@@ -0,0 +1,656 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::{
+    client::CloudflareApi,
+    diff::EnvVarsDiff,
+    patch::{split_patch, PatchEntryFilter, PatchMode},
+    retry::RetryOn,
+    summary::ChangeSummary,
+    types::{
+        CloudflarePagesDeploymentConfigs, CloudflarePagesPatchRequest, EnvVarsFile, Environment,
+    },
+    validation::find_missing_keys,
+};
+
+/// Downloads the environment variables of a project, or of a single deployment if one is
+/// specified. Since a deployment only targets one environment, the other one is left as `None`.
+pub fn fetch_env_vars<A>(api: &A, project: &str, deployment: Option<&str>) -> Result<EnvVarsFile>
+where
+    A: CloudflareApi,
+{
+    Ok(match deployment {
+        Some(deployment) => api.get_deployment(project, deployment)?.into(),
+        None => api.get_project(project)?.deployment_configs.into(),
+    })
+}
+
+/// Compares the project's current environment variables against `new_vars` without changing
+/// anything.
+pub fn diff_env_vars<A>(
+    api: &A,
+    project: &str,
+    new_vars: &EnvVarsFile,
+    mode: PatchMode,
+) -> Result<EnvVarsDiff>
+where
+    A: CloudflareApi,
+{
+    Ok(EnvVarsDiff::new(
+        api.get_project(project)?.deployment_configs.into(),
+        new_vars,
+        mode,
+    ))
+}
+
+/// Options controlling how [`apply_env_vars`] submits changes.
+#[derive(Debug, Clone)]
+pub struct ApplyOptions {
+    /// Maximum number of variables per PATCH request. The patch is sent in a single request if
+    /// `None`.
+    pub max_vars_per_request: Option<usize>,
+    /// Whether variables missing from `new_vars` are deleted or left untouched.
+    pub mode: PatchMode,
+    /// Number of times failed requests are retried in total, across all requests.
+    pub max_retries: usize,
+    /// Failures of PATCH requests that are retried.
+    pub patch_retry_on: RetryOn,
+    /// Failures of GET requests fetching the remote variables that are retried.
+    pub get_retry_on: RetryOn,
+    /// Delay before each retry.
+    pub retry_delay: Duration,
+    /// Maximum wall-clock time of the whole operation, including retries. A failure is no longer
+    /// retried once the delay before the retry would exceed it.
+    pub retry_budget: Option<Duration>,
+    /// Whether to fetch the remote state again and recompute the patch before retrying. A failed
+    /// request might have been applied with only its response lost, so resending it as-is could
+    /// apply a stale diff.
+    pub recompute_on_retry: bool,
+    /// Keys that must be present in both environments after the update. Checked before
+    /// submitting anything.
+    pub required_keys: Vec<String>,
+    /// Keys managed elsewhere, which are never added, changed or deleted. Takes precedence over
+    /// `required_keys`, which are checked against the result.
+    pub ignored_keys: Vec<String>,
+    /// Applies only part of the changes if set.
+    pub entry_filter: Option<PatchEntryFilter>,
+}
+
+/// Brings the project's environment variables in line with `new_vars`, submitting a patch only
+/// when there are changes. Returns a summary of the applied changes, which is empty if no patch was
+/// submitted. When the patch is recomputed on retry, this includes what the failed request turned
+/// out to have applied.
+///
+/// `on_progress` is called with the number of completed and total requests after each request. The
+/// total might change when the patch is recomputed on retry.
+pub fn apply_env_vars<A, F>(
+    api: &A,
+    project: &str,
+    new_vars: &EnvVarsFile,
+    options: &ApplyOptions,
+    mut on_progress: F,
+) -> Result<ChangeSummary>
+where
+    A: CloudflareApi,
+    F: FnMut(usize, usize),
+{
+    let mut retries = Retries::new(options);
+    let diff = diff_with_retries(api, project, new_vars, options, &mut retries)?;
+    check_required_keys(&diff, &options.required_keys)?;
+
+    let mut applied = ChangeSummary::default();
+    let mut old_vars = diff.old_vars;
+    let mut chunks = split_into_chunks(diff.patch, options);
+
+    // Cloudflare merges `env_vars` into the existing config, so each chunk stands on its own
+    let mut ind = 0;
+    while ind < chunks.len() {
+        let request = CloudflarePagesPatchRequest {
+            deployment_configs: chunks[ind].clone(),
+        };
+        match api.patch_project(project, &request) {
+            Ok(_) => {
+                applied.merge(ChangeSummary::from_patch(&old_vars, &chunks[ind]));
+                ind += 1;
+                on_progress(ind, chunks.len());
+            }
+            Err(err) if retries.try_retry(options.patch_retry_on, &err) => {
+                if options.recompute_on_retry {
+                    // Chunks already applied (including possibly the failed one) drop out of the
+                    // recomputed patch
+                    let diff = diff_with_retries(api, project, new_vars, options, &mut retries)?;
+                    check_required_keys(&diff, &options.required_keys)?;
+                    applied.merge(
+                        ChangeSummary::from_patch(&old_vars, &chunks[ind])
+                            .without_keys_in(&diff.patch),
+                    );
+                    old_vars = diff.old_vars;
+                    chunks = split_into_chunks(diff.patch, options);
+                    ind = 0;
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Runs [`diff_env_vars`] without the ignored keys and filtered entries, retrying failures
+/// matching `get_retry_on` out of the shared budget of `max_retries`.
+fn diff_with_retries<A>(
+    api: &A,
+    project: &str,
+    new_vars: &EnvVarsFile,
+    options: &ApplyOptions,
+    retries: &mut Retries,
+) -> Result<EnvVarsDiff>
+where
+    A: CloudflareApi,
+{
+    loop {
+        match diff_env_vars(api, project, new_vars, options.mode) {
+            Ok(diff) => {
+                let diff = diff.ignoring_keys(&options.ignored_keys);
+                return Ok(match options.entry_filter {
+                    Some(filter) => diff.only(filter),
+                    None => diff,
+                });
+            }
+            Err(err) if retries.try_retry(options.get_retry_on, &err) => {}
+            result => return result,
+        }
+    }
+}
+
+/// Retries spent so far by a single [`apply_env_vars`] call.
+struct Retries {
+    count: usize,
+    max_retries: usize,
+    delay: Duration,
+    deadline: Option<Instant>,
+}
+
+impl Retries {
+    fn new(options: &ApplyOptions) -> Self {
+        Self {
+            count: 0,
+            max_retries: options.max_retries,
+            delay: options.retry_delay,
+            deadline: options.retry_budget.map(|budget| Instant::now() + budget),
+        }
+    }
+
+    /// Checks whether `err` is worth retrying with the remaining budget, and if so, counts the
+    /// retry and waits before it.
+    fn try_retry(&mut self, retry_on: RetryOn, err: &anyhow::Error) -> bool {
+        if self.count >= self.max_retries || !retry_on.matches(err) {
+            return false;
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() + self.delay > deadline)
+        {
+            return false;
+        }
+
+        self.count += 1;
+        std::thread::sleep(self.delay);
+        true
+    }
+}
+
+/// Fails if any required key would be absent once the patch is applied.
+pub fn check_required_keys(diff: &EnvVarsDiff, required_keys: &[String]) -> Result<()> {
+    let problems = [
+        (
+            Environment::Production,
+            &diff.old_vars.production,
+            &diff.patch.production,
+        ),
+        (
+            Environment::Preview,
+            &diff.old_vars.preview,
+            &diff.patch.preview,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(environment, old_env, patch_env)| {
+        let changes = patch_env.env_vars.as_ref();
+        let resulting_keys = old_env
+            .keys()
+            .filter(|key| !changes.is_some_and(|changes| changes.get(*key) == Some(&None)))
+            .chain(
+                changes
+                    .into_iter()
+                    .flatten()
+                    .filter(|(_, value)| value.is_some())
+                    .map(|(key, _)| key),
+            );
+        let missing_keys = find_missing_keys(resulting_keys, required_keys);
+        if missing_keys.is_empty() {
+            None
+        } else {
+            Some(format!("{}: {}", environment, missing_keys.join(", ")))
+        }
+    })
+    .collect::<Vec<_>>();
+
+    if !problems.is_empty() {
+        anyhow::bail!("missing required keys in {}", problems.join("; "));
+    }
+
+    Ok(())
+}
+
+fn split_into_chunks(
+    patch: CloudflarePagesDeploymentConfigs,
+    options: &ApplyOptions,
+) -> Vec<CloudflarePagesDeploymentConfigs> {
+    if patch.is_empty() {
+        return vec![];
+    }
+
+    match options.max_vars_per_request {
+        Some(max_vars) => split_patch(&patch, max_vars),
+        None => vec![patch],
+    }
+}
+
+impl Default for ApplyOptions {
+    fn default() -> Self {
+        Self {
+            max_vars_per_request: None,
+            mode: PatchMode::Replace,
+            max_retries: 0,
+            patch_retry_on: RetryOn::PATCH_DEFAULT,
+            get_retry_on: RetryOn::GET_DEFAULT,
+            retry_delay: Duration::from_secs(1),
+            retry_budget: None,
+            recompute_on_retry: true,
+            required_keys: vec![],
+            ignored_keys: vec![],
+            entry_filter: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        test_utils::{vars, FakeApi},
+        types::EnvVar,
+    };
+
+    #[test]
+    fn test_fetch_env_vars_from_project() {
+        let api = FakeApi::new("my-project", vars(&[("A", "1")]), vars(&[("B", "2")]));
+
+        let env_vars = fetch_env_vars(&api, "my-project", None).unwrap();
+
+        assert_eq!(env_vars.production, Some(vars(&[("A", "1")])));
+        assert_eq!(env_vars.preview, Some(vars(&[("B", "2")])));
+    }
+
+    #[test]
+    fn test_fetch_env_vars_from_deployment() {
+        let api = FakeApi::new("my-project", vars(&[("A", "1")]), vars(&[("B", "2")]))
+            .with_deployment(
+                "abc",
+                Environment::Preview,
+                "2023-04-25T08:30:00Z",
+                vars(&[("B", "old")]),
+            );
+
+        let env_vars = fetch_env_vars(&api, "my-project", Some("abc")).unwrap();
+
+        assert_eq!(env_vars.production, None);
+        assert_eq!(env_vars.preview, Some(vars(&[("B", "old")])));
+    }
+
+    #[test]
+    fn test_fetch_env_vars_unknown_project() {
+        let api = FakeApi::new("my-project", vars(&[]), vars(&[]));
+
+        assert!(fetch_env_vars(&api, "other-project", None).is_err());
+    }
+
+    #[test]
+    fn test_apply_env_vars() {
+        let api = FakeApi::new(
+            "my-project",
+            vars(&[("KEEP", "1"), ("CHANGE", "old"), ("REMOVE", "x")]),
+            vars(&[("PREVIEW", "1")]),
+        );
+
+        let summary = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("KEEP", "1"), ("CHANGE", "new"), ("ADD", "2")])),
+                preview: None,
+            },
+            &ApplyOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert_eq!(summary.production.added, vec!["ADD".to_owned()]);
+        assert_eq!(summary.production.changed, vec!["CHANGE".to_owned()]);
+        assert_eq!(summary.production.removed, vec!["REMOVE".to_owned()]);
+        assert_eq!(api.patch_count(), 1);
+
+        let env_vars = fetch_env_vars(&api, "my-project", None).unwrap();
+        assert_eq!(
+            env_vars.production,
+            Some(vars(&[("KEEP", "1"), ("CHANGE", "new"), ("ADD", "2")]))
+        );
+        assert_eq!(env_vars.preview, Some(vars(&[("PREVIEW", "1")])));
+    }
+
+    #[test]
+    fn test_apply_env_vars_chunked() {
+        let api = FakeApi::new("my-project", vars(&[("REMOVE", "x")]), vars(&[]));
+
+        let mut progress = vec![];
+        apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1"), ("B", "2"), ("C", "3")])),
+                preview: Some(vars(&[("D", "4")])),
+            },
+            &ApplyOptions {
+                max_vars_per_request: Some(2),
+                ..Default::default()
+            },
+            |done, total| progress.push((done, total)),
+        )
+        .unwrap();
+
+        assert_eq!(api.patch_count(), 3);
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+
+        let env_vars = fetch_env_vars(&api, "my-project", None).unwrap();
+        assert_eq!(
+            env_vars.production,
+            Some(vars(&[("A", "1"), ("B", "2"), ("C", "3")]))
+        );
+        assert_eq!(env_vars.preview, Some(vars(&[("D", "4")])));
+    }
+
+    #[test]
+    fn test_apply_env_vars_no_changes() {
+        let api = FakeApi::new("my-project", vars(&[("A", "1")]), vars(&[("B", "2")]));
+
+        let summary = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1")])),
+                preview: Some(vars(&[("B", "2")])),
+            },
+            &ApplyOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert!(summary.is_empty());
+        assert_eq!(api.patch_count(), 0);
+    }
+
+    #[test]
+    fn test_apply_env_vars_recompute_on_retry() {
+        let api = FakeApi::new("my-project", vars(&[("A", "1")]), vars(&[])).with_lost_responses(1);
+
+        let mut progress = vec![];
+        let summary = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "2"), ("B", "3")])),
+                preview: None,
+            },
+            &ApplyOptions {
+                max_retries: 1,
+                patch_retry_on: RetryOn::TIMEOUT,
+                retry_delay: Duration::ZERO,
+                ..Default::default()
+            },
+            |done, total| progress.push((done, total)),
+        )
+        .unwrap();
+
+        // The first request went through, so recomputing leaves nothing to resend
+        assert_eq!(summary.production.added, vec!["B".to_owned()]);
+        assert_eq!(summary.production.changed, vec!["A".to_owned()]);
+        assert!(summary.preview.is_empty());
+        assert_eq!(api.patch_count(), 1);
+        assert!(progress.is_empty());
+
+        let env_vars = fetch_env_vars(&api, "my-project", None).unwrap();
+        assert_eq!(env_vars.production, Some(vars(&[("A", "2"), ("B", "3")])));
+    }
+
+    #[test]
+    fn test_apply_env_vars_recompute_on_retry_chunked() {
+        let api = FakeApi::new("my-project", vars(&[("A", "1"), ("C", "x")]), vars(&[]))
+            .with_lost_responses(1);
+
+        let summary = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "2"), ("B", "3")])),
+                preview: None,
+            },
+            &ApplyOptions {
+                max_vars_per_request: Some(1),
+                max_retries: 1,
+                patch_retry_on: RetryOn::TIMEOUT,
+                retry_delay: Duration::ZERO,
+                ..Default::default()
+            },
+            |_, _| {},
+        )
+        .unwrap();
+
+        // The lost first request is accounted for once, alongside the two resubmitted ones
+        assert_eq!(summary.production.added, vec!["B".to_owned()]);
+        assert_eq!(summary.production.changed, vec!["A".to_owned()]);
+        assert_eq!(summary.production.removed, vec!["C".to_owned()]);
+        assert!(summary.preview.is_empty());
+        assert_eq!(api.patch_count(), 3);
+    }
+
+    #[test]
+    fn test_apply_env_vars_timeouts_not_retried_by_default() {
+        let api = FakeApi::new("my-project", vars(&[]), vars(&[])).with_lost_responses(1);
+
+        let result = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1")])),
+                preview: None,
+            },
+            &ApplyOptions {
+                max_retries: 1,
+                retry_delay: Duration::ZERO,
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        assert!(result.is_err());
+        assert_eq!(api.patch_count(), 1);
+    }
+
+    #[test]
+    fn test_apply_env_vars_retries_exhausted() {
+        let api = FakeApi::new("my-project", vars(&[]), vars(&[])).with_lost_responses(2);
+
+        let result = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1"), ("B", "2")])),
+                preview: None,
+            },
+            &ApplyOptions {
+                max_vars_per_request: Some(1),
+                max_retries: 1,
+                patch_retry_on: RetryOn::TIMEOUT,
+                retry_delay: Duration::ZERO,
+                recompute_on_retry: false,
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        assert!(result.is_err());
+        assert_eq!(api.patch_count(), 2);
+    }
+
+    #[test]
+    fn test_apply_env_vars_retry_budget_exhausted() {
+        let api = FakeApi::new("my-project", vars(&[]), vars(&[])).with_lost_responses(1);
+
+        let result = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1")])),
+                preview: None,
+            },
+            &ApplyOptions {
+                max_retries: 5,
+                patch_retry_on: RetryOn::TIMEOUT,
+                retry_delay: Duration::from_secs(60),
+                retry_budget: Some(Duration::from_secs(30)),
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        assert!(result.is_err());
+        assert_eq!(api.patch_count(), 1);
+    }
+
+    #[test]
+    fn test_apply_env_vars_missing_required_keys() {
+        let api = FakeApi::new(
+            "my-project",
+            vars(&[("DATABASE_URL", "1")]),
+            vars(&[("A", "1")]),
+        );
+
+        let err = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1")])),
+                preview: None,
+            },
+            &ApplyOptions {
+                required_keys: vec!["DATABASE_URL".to_owned(), "A".to_owned()],
+                ..Default::default()
+            },
+            |_, _| {},
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "missing required keys in production: DATABASE_URL; preview: DATABASE_URL"
+        );
+        assert_eq!(api.patch_count(), 0);
+    }
+
+    #[test]
+    fn test_apply_env_vars_required_keys_from_untouched_environment() {
+        let api = FakeApi::new("my-project", vars(&[]), vars(&[("DATABASE_URL", "1")]));
+
+        apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(vars(&[("DATABASE_URL", "2")])),
+                preview: None,
+            },
+            &ApplyOptions {
+                required_keys: vec!["DATABASE_URL".to_owned()],
+                ..Default::default()
+            },
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert_eq!(api.patch_count(), 1);
+    }
+
+    #[test]
+    fn test_apply_env_vars_merge() {
+        let api = FakeApi::new(
+            "my-project",
+            vars(&[("KEEP", "1"), ("REMOVE", "x")]),
+            vars(&[("KEEP", "1")]),
+        );
+
+        let mut production: BTreeMap<String, Option<EnvVar>> = vars(&[("ADD", "2")]);
+        production.insert("REMOVE".to_owned(), None);
+        let summary = apply_env_vars(
+            &api,
+            "my-project",
+            &EnvVarsFile {
+                production: Some(production),
+                preview: None,
+            },
+            &ApplyOptions {
+                mode: PatchMode::Merge,
+                required_keys: vec!["KEEP".to_owned()],
+                ..Default::default()
+            },
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert_eq!(summary.production.added, vec!["ADD".to_owned()]);
+        assert_eq!(summary.production.removed, vec!["REMOVE".to_owned()]);
+
+        let env_vars = fetch_env_vars(&api, "my-project", None).unwrap();
+        assert_eq!(
+            env_vars.production,
+            Some(vars(&[("KEEP", "1"), ("ADD", "2")]))
+        );
+    }
+
+    #[test]
+    fn test_round_trip_with_secrets() {
+        let mut production = vars(&[("PLAIN", "1")]);
+        production.insert("SECRET".to_owned(), EnvVar::secret("hunter2".to_owned()));
+        let api = FakeApi::new("my-project", production, vars(&[("B", "2")]));
+
+        let env_vars = fetch_env_vars(&api, "my-project", None).unwrap();
+        assert_eq!(
+            env_vars.production.as_ref().unwrap()["SECRET"],
+            Some(EnvVar::secret(String::new()))
+        );
+
+        let summary = apply_env_vars(
+            &api,
+            "my-project",
+            &env_vars,
+            &ApplyOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert!(summary.is_empty());
+        assert_eq!(api.patch_count(), 0);
+    }
+}
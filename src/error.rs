@@ -0,0 +1,120 @@
+use thiserror::Error;
+
+/// Failures callers may want to tell apart. They are returned wrapped in [`anyhow::Error`], and
+/// can be recovered from it with [`CfPagesError::find`].
+#[derive(Debug, Error)]
+pub enum CfPagesError {
+    /// The token was rejected, or lacks the permissions needed for the request.
+    #[error(
+        "request not authorized (HTTP status {status}). Check that the token is valid and has the \
+        Cloudflare Pages:Edit permission"
+    )]
+    Unauthorized { status: u16 },
+    /// The requested project does not exist in the account.
+    #[error("project '{project}' not found in account '{account}'")]
+    ProjectNotFound { project: String, account: String },
+    /// The requested deployment does not exist in the project.
+    #[error("deployment '{deployment}' not found in project '{project}'")]
+    DeploymentNotFound { project: String, deployment: String },
+    /// Cloudflare rejected the request because of rate limiting.
+    #[error("rate limited by Cloudflare")]
+    RateLimited,
+    /// The request timed out.
+    #[error("request timed out: {0}")]
+    Timeout(String),
+    /// No connection could be established, so the request never reached Cloudflare.
+    #[error("unable to connect: {0}")]
+    Connect(String),
+    /// Cloudflare failed to process the request because of a server-side error.
+    #[error("server error (HTTP status {status})")]
+    ServerError { status: u16 },
+    /// The API responded with an unexpected status, or reported the request as unsuccessful.
+    #[error("Cloudflare API error: {0}")]
+    Api(String),
+    /// The request could not be sent or its response could not be read. Converted from
+    /// [`reqwest::Error`] by hand, as connection failures and timeouts get variants of their own.
+    #[error("HTTP request failed: {0}")]
+    Http(#[source] reqwest::Error),
+    /// The response body could not be parsed.
+    #[error("unable to parse response: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl CfPagesError {
+    /// Finds the first [`CfPagesError`] in the chain of an error.
+    pub fn find(error: &anyhow::Error) -> Option<&Self> {
+        error.chain().find_map(|err| err.downcast_ref::<Self>())
+    }
+
+    /// Short name of the kind of failure, for summaries of many failed operations.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Unauthorized { .. } => "unauthorized",
+            Self::ProjectNotFound { .. } | Self::DeploymentNotFound { .. } => "not found",
+            Self::RateLimited => "rate limited",
+            Self::Timeout(_) => "timeout",
+            Self::Connect(_) => "connect",
+            Self::ServerError { .. } => "server error",
+            Self::Api(_) => "api",
+            Self::Http(_) => "http",
+            Self::Serde(_) => "invalid response",
+            Self::Io(_) => "io",
+        }
+    }
+
+    /// Checks whether an error, or any error in its chain, may succeed if retried.
+    pub fn is_transient(error: &anyhow::Error) -> bool {
+        matches!(
+            Self::find(error),
+            Some(
+                Self::RateLimited | Self::Timeout(_) | Self::Connect(_) | Self::ServerError { .. }
+            )
+        )
+    }
+}
+
+impl From<reqwest::Error> for CfPagesError {
+    fn from(value: reqwest::Error) -> Self {
+        // Checked first, as connection timeouts never reached the server either
+        if value.is_connect() {
+            Self::Connect(value.to_string())
+        } else if value.is_timeout() {
+            Self::Timeout(value.to_string())
+        } else {
+            Self::Http(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_find_through_context() {
+        let err = Err::<(), _>(CfPagesError::RateLimited)
+            .context("unable to update project")
+            .unwrap_err();
+
+        assert!(matches!(
+            CfPagesError::find(&err),
+            Some(CfPagesError::RateLimited)
+        ));
+        assert!(CfPagesError::is_transient(&err));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(CfPagesError::is_transient(
+            &CfPagesError::Timeout("timeout".to_owned()).into()
+        ));
+        assert!(!CfPagesError::is_transient(
+            &CfPagesError::Unauthorized { status: 403 }.into()
+        ));
+        assert!(!CfPagesError::is_transient(&anyhow::anyhow!("other")));
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Serialization formats supported for local environment variable files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileFormat {
+    Json,
+    Toml,
+}
+
+impl FileFormat {
+    /// Guesses the format from the file extension, falling back to JSON.
+    pub fn from_path<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+
+    /// Serializes the value in a human-readable form. The output does not end with a newline.
+    pub fn serialize<T>(&self, value: &T) -> Result<String>
+    where
+        T: Serialize,
+    {
+        let content = match self {
+            Self::Json => serde_json::to_string_pretty(value)?,
+            Self::Toml => toml::to_string_pretty(value)?,
+        };
+
+        Ok(content.trim_end_matches('\n').to_owned())
+    }
+
+    pub fn deserialize<T>(&self, content: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(match self {
+            Self::Json => serde_json::from_str(content)?,
+            Self::Toml => toml::from_str(content)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{EnvVar, EnvVarsFile};
+
+    #[test]
+    fn test_toml_round_trip() {
+        let vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                (
+                    "API_URL".to_owned(),
+                    Some(EnvVar::plain("https://example.com".to_owned())),
+                ),
+                (
+                    "with space".to_owned(),
+                    Some(EnvVar::plain("multi\nline".to_owned())),
+                ),
+                ("SECRET".to_owned(), Some(EnvVar::secret("".to_owned()))),
+            ])),
+            preview: Some(BTreeMap::new()),
+        };
+
+        let content = FileFormat::Toml.serialize(&vars).unwrap();
+        let parsed: EnvVarsFile = FileFormat::Toml.deserialize(&content).unwrap();
+
+        assert_eq!(parsed, vars);
+    }
+
+    #[test]
+    fn test_toml_round_trip_missing_environment() {
+        let vars = EnvVarsFile {
+            production: None,
+            preview: Some(BTreeMap::from([(
+                "A".to_owned(),
+                Some(EnvVar::plain("1".to_owned())),
+            )])),
+        };
+
+        let content = FileFormat::Toml.serialize(&vars).unwrap();
+        let parsed: EnvVarsFile = FileFormat::Toml.deserialize(&content).unwrap();
+
+        assert_eq!(parsed, vars);
+    }
+
+    #[test]
+    fn test_json_plain_secret_and_deleted_values() {
+        let vars: EnvVarsFile = FileFormat::Json
+            .deserialize(
+                r#"{"production":{"A":"1","B":{"value":"2","secret":true},"C":null},"preview":null}"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            vars.production,
+            Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("1".to_owned()))),
+                ("B".to_owned(), Some(EnvVar::secret("2".to_owned()))),
+                ("C".to_owned(), None),
+            ]))
+        );
+        assert_eq!(
+            FileFormat::Json.serialize(&vars).unwrap(),
+            r#"{
+  "production": {
+    "A": "1",
+    "B": {
+      "value": "2",
+      "secret": true
+    },
+    "C": null
+  },
+  "preview": null
+}"#
+        );
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(FileFormat::from_path("vars.toml"), FileFormat::Toml);
+        assert_eq!(FileFormat::from_path("vars.json"), FileFormat::Json);
+        assert_eq!(FileFormat::from_path("vars"), FileFormat::Json);
+    }
+}
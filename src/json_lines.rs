@@ -0,0 +1,91 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::types::{EnvVar, EnvVarsFile};
+
+/// A single variable assignment in newline-delimited JSON input.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonLine {
+    env: String,
+    key: String,
+    /// `null` marks the variable for deletion.
+    value: Option<String>,
+    #[serde(default)]
+    secret: bool,
+}
+
+/// Parses newline-delimited JSON objects of the form
+/// `{ "env": "production", "key": "...", "value": "..." }` into a file of variables. Blank lines
+/// are skipped, and later lines override earlier ones for the same key. Only the environments
+/// mentioned by at least one line are present in the result.
+pub fn parse_json_lines(content: &str) -> Result<EnvVarsFile> {
+    let mut vars = EnvVarsFile::default();
+
+    for (ind, line) in content.lines().enumerate() {
+        let line_number = ind + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: JsonLine = serde_json::from_str(line)
+            .map_err(|err| anyhow::anyhow!("line {line_number}: {err}"))?;
+        let env = match entry.env.as_str() {
+            "production" => &mut vars.production,
+            "preview" => &mut vars.preview,
+            other => anyhow::bail!(
+                "line {line_number}: unknown environment {other:?}, expected production or preview"
+            ),
+        };
+        let var = entry.value.map(|value| EnvVar {
+            value,
+            secret: entry.secret,
+        });
+        env.get_or_insert_with(Default::default)
+            .insert(entry.key, var);
+    }
+
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_json_lines() {
+        let content = r#"{"env":"production","key":"A","value":"1"}
+
+{"env":"production","key":"B","value":"2","secret":true}
+{"env":"production","key":"A","value":"3"}
+{"env":"production","key":"OLD","value":null}
+"#;
+
+        let vars = parse_json_lines(content).unwrap();
+
+        assert_eq!(
+            vars.production,
+            Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("3".to_owned()))),
+                ("B".to_owned(), Some(EnvVar::secret("2".to_owned()))),
+                ("OLD".to_owned(), None),
+            ]))
+        );
+        assert_eq!(vars.environments().count(), 1);
+        assert!(vars.preview.is_none());
+    }
+
+    #[test]
+    fn test_parse_json_lines_errors() {
+        let err = parse_json_lines("{\"env\":\"preview\",\"key\":\"A\",\"value\":\"1\"}\nnot json")
+            .unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"));
+
+        let err = parse_json_lines(r#"{"env":"staging","key":"A","value":"1"}"#).unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"));
+
+        assert!(parse_json_lines(r#"{"env":"preview","value":"1"}"#).is_err());
+    }
+}
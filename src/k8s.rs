@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::dotenv::LineEnding;
+
+/// Kubernetes object holding variables as its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum K8sManifestKind {
+    /// A `Secret` of type `Opaque`, with base64-encoded values.
+    Secret,
+    /// A `ConfigMap`, with values as-is.
+    ConfigMap,
+}
+
+/// Renders a YAML manifest of a single Kubernetes object holding `vars`. Keys and values are
+/// written as double-quoted scalars, so that no value is ever reinterpreted as a number or
+/// boolean.
+pub fn format_k8s_manifest(
+    kind: K8sManifestKind,
+    name: &str,
+    namespace: Option<&str>,
+    vars: &BTreeMap<String, String>,
+    line_ending: LineEnding,
+) -> String {
+    let mut lines = vec!["apiVersion: v1".to_owned()];
+    lines.push(match kind {
+        K8sManifestKind::Secret => "kind: Secret".to_owned(),
+        K8sManifestKind::ConfigMap => "kind: ConfigMap".to_owned(),
+    });
+    lines.push("metadata:".to_owned());
+    lines.push(format!("  name: {}", quote(name)));
+    if let Some(namespace) = namespace {
+        lines.push(format!("  namespace: {}", quote(namespace)));
+    }
+    if kind == K8sManifestKind::Secret {
+        lines.push("type: Opaque".to_owned());
+    }
+
+    if vars.is_empty() {
+        lines.push("data: {}".to_owned());
+    } else {
+        lines.push("data:".to_owned());
+        for (key, value) in vars.iter() {
+            let value = match kind {
+                K8sManifestKind::Secret => BASE64.encode(value.as_bytes()),
+                K8sManifestKind::ConfigMap => value.to_owned(),
+            };
+            lines.push(format!("  {}: {}", quote(key), quote(&value)));
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|line| format!("{line}{}", line_ending.as_str()))
+        .collect()
+}
+
+/// JSON strings are valid YAML double-quoted scalars.
+fn quote(value: &str) -> String {
+    serde_json::Value::String(value.to_owned()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("API_URL".to_owned(), "https://example.com".to_owned()),
+            ("PORT".to_owned(), "8080".to_owned()),
+            ("QUOTED".to_owned(), "say \"hi\"\n".to_owned()),
+        ])
+    }
+
+    #[test]
+    fn test_format_k8s_secret() {
+        assert_eq!(
+            format_k8s_manifest(
+                K8sManifestKind::Secret,
+                "my-app",
+                Some("staging"),
+                &vars(),
+                LineEnding::Lf
+            ),
+            r#"apiVersion: v1
+kind: Secret
+metadata:
+  name: "my-app"
+  namespace: "staging"
+type: Opaque
+data:
+  "API_URL": "aHR0cHM6Ly9leGFtcGxlLmNvbQ=="
+  "PORT": "ODA4MA=="
+  "QUOTED": "c2F5ICJoaSIK"
+"#
+        );
+    }
+
+    #[test]
+    fn test_format_k8s_configmap() {
+        assert_eq!(
+            format_k8s_manifest(
+                K8sManifestKind::ConfigMap,
+                "my-app",
+                None,
+                &vars(),
+                LineEnding::Lf
+            ),
+            r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: "my-app"
+data:
+  "API_URL": "https://example.com"
+  "PORT": "8080"
+  "QUOTED": "say \"hi\"\n"
+"#
+        );
+        assert!(format_k8s_manifest(
+            K8sManifestKind::ConfigMap,
+            "empty",
+            None,
+            &BTreeMap::new(),
+            LineEnding::Lf
+        )
+        .ends_with("data: {}\n"));
+    }
+}
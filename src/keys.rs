@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+/// Case conversion applied to variable names, for tools that only accept one case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum KeyTransform {
+    Uppercase,
+    Lowercase,
+}
+
+impl KeyTransform {
+    pub fn apply(self, key: &str) -> String {
+        match self {
+            Self::Uppercase => key.to_uppercase(),
+            Self::Lowercase => key.to_lowercase(),
+        }
+    }
+}
+
+/// Renames all keys with `transform`. Fails if two keys end up with the same name, as one of the
+/// values would silently be lost otherwise.
+pub fn transform_keys<V>(
+    vars: BTreeMap<String, V>,
+    transform: KeyTransform,
+) -> Result<BTreeMap<String, V>> {
+    let mut original_keys = BTreeMap::new();
+    let mut transformed = BTreeMap::new();
+
+    for (key, value) in vars.into_iter() {
+        let new_key = transform.apply(&key);
+        if let Some(other) = original_keys.insert(new_key.clone(), key.clone()) {
+            anyhow::bail!("keys {other} and {key} both become {new_key}");
+        }
+        transformed.insert(new_key, value);
+    }
+
+    Ok(transformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform_keys() {
+        let vars = BTreeMap::from([
+            ("api_url".to_owned(), 1),
+            ("Port".to_owned(), 2),
+            ("DEBUG".to_owned(), 3),
+        ]);
+
+        assert_eq!(
+            transform_keys(vars, KeyTransform::Uppercase).unwrap(),
+            BTreeMap::from([
+                ("API_URL".to_owned(), 1),
+                ("PORT".to_owned(), 2),
+                ("DEBUG".to_owned(), 3),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_transform_keys_collision() {
+        let vars = BTreeMap::from([("API_URL".to_owned(), 1), ("api_url".to_owned(), 2)]);
+
+        let err = transform_keys(vars, KeyTransform::Lowercase).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "keys API_URL and api_url both become api_url"
+        );
+    }
+}
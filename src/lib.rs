@@ -0,0 +1,86 @@
+//! Library powering the `cf-pages` command line utility for managing Cloudflare Pages projects.
+
+mod cache;
+mod client;
+mod compare;
+mod config;
+mod deployments;
+mod diff;
+mod dotenv;
+mod env_vars;
+mod error;
+mod expand;
+mod format;
+mod json_lines;
+mod k8s;
+mod keys;
+mod netlify;
+mod output;
+mod patch;
+mod pattern;
+mod pointer;
+mod project;
+mod retry;
+mod state;
+mod summary;
+mod template;
+mod time;
+mod types;
+mod validation;
+mod wrangler;
+
+#[cfg(test)]
+mod test_utils;
+
+pub use cache::CachedApi;
+pub use client::{CloudflareApi, CloudflareClient, DEFAULT_API_BASE_URL};
+pub use compare::EnvironmentComparison;
+pub use config::{
+    default_config_path, set_config_value, show_config, write_file_atomically, Config, ConfigKey,
+};
+pub use deployments::{for_each_deployment, list_deployments, DeploymentFilter};
+pub use diff::EnvVarsDiff;
+pub use dotenv::{
+    collect_comments, find_duplicate_keys, format_comment, format_envsubst_vars,
+    format_properties_line, format_shell_line, format_systemd_line, parse_dotenv, DotenvEntry,
+    EnvFileQuote, LineEnding,
+};
+pub use env_vars::{
+    apply_env_vars, check_required_keys, diff_env_vars, fetch_env_vars, ApplyOptions,
+};
+pub use error::CfPagesError;
+pub use expand::{
+    command_value, expand_env_refs, file_value, resolve_value, run_value_command,
+    COMMAND_VALUE_PREFIX, FILE_VALUE_PREFIX,
+};
+pub use format::FileFormat;
+pub use json_lines::parse_json_lines;
+pub use k8s::{format_k8s_manifest, K8sManifestKind};
+pub use keys::{transform_keys, KeyTransform};
+pub use netlify::{NetlifyConfig, NetlifyEnvironmentSection};
+pub use output::{create_output_file, ensure_parent_dir, parse_file_mode, SECRET_FILE_MODE};
+pub use patch::{
+    generate_deployment_configs_patch, generate_env_patch, split_patch, PatchEntryFilter, PatchMode,
+};
+pub use pattern::{expand_path_glob, glob_match, is_glob};
+pub use pointer::{extract_json_pointer, select_fields};
+pub use project::ensure_project;
+pub use retry::RetryOn;
+pub use state::{read_state_hash, state_hash};
+pub use summary::{ChangeSummary, EnvChanges};
+pub use template::PathTemplate;
+pub use time::{parse_duration, parse_rfc3339};
+pub use types::{
+    CloudflareAccount, CloudflareApiToken, CloudflarePagesCreateProjectRequest,
+    CloudflarePagesDeployment, CloudflarePagesDeploymentConfigs, CloudflarePagesEnvVarValue,
+    CloudflarePagesEnvVarValueType, CloudflarePagesEnvironment, CloudflarePagesPatchRequest,
+    CloudflarePagesProject, CloudflarePermissionGroup, CloudflareResponse, CloudflareResultInfo,
+    CloudflareTokenPolicy, CloudflareTokenVerification, EnvKeysFile, EnvVar, EnvVarsFile,
+    Environment, EnvironmentInfo, FullEnvVarsFile, ListPage, ProjectInfo,
+};
+pub use validation::{
+    find_case_collisions, find_missing_keys, find_missing_pages_permissions, find_oversized_values,
+    find_var_limit_violations, is_valid_key, validate_env_vars, VarLimitExceeded, MAX_VALUE_SIZE,
+    MAX_VARS_PER_ENVIRONMENT, PAGES_READ_PERMISSION, PAGES_WRITE_PERMISSION,
+};
+pub use wrangler::{project_name_from_wrangler, DEFAULT_WRANGLER_PATH};
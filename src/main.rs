@@ -1,72 +1,740 @@
-use std::{collections::BTreeMap, io::Write, path::PathBuf, str::FromStr, time::Duration};
+use std::{
+    collections::BTreeMap,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::Result;
-use clap::{builder::PossibleValue, Parser, Subcommand, ValueEnum};
-use reqwest::blocking::ClientBuilder;
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use cf_pages_cli::{
+    apply_env_vars, check_required_keys, collect_comments, command_value, create_output_file,
+    default_config_path, diff_env_vars, ensure_parent_dir, ensure_project, expand_env_refs,
+    expand_path_glob, extract_json_pointer, fetch_env_vars, file_value, find_case_collisions,
+    find_duplicate_keys, find_missing_pages_permissions, find_oversized_values,
+    find_var_limit_violations, for_each_deployment, format_comment, format_envsubst_vars,
+    format_k8s_manifest, format_properties_line, format_shell_line, format_systemd_line,
+    glob_match, is_glob, parse_dotenv, parse_duration, parse_file_mode, parse_json_lines,
+    project_name_from_wrangler, read_state_hash, resolve_value, select_fields, set_config_value,
+    show_config, state_hash, transform_keys, validate_env_vars, write_file_atomically,
+    ApplyOptions, CachedApi, CfPagesError, ChangeSummary, CloudflareApi, CloudflareClient,
+    CloudflarePagesPatchRequest, Config, ConfigKey, DeploymentFilter, EnvFileQuote, EnvKeysFile,
+    EnvVar, EnvVarsDiff, EnvVarsFile, Environment, EnvironmentComparison, FileFormat,
+    FullEnvVarsFile, K8sManifestKind, KeyTransform, LineEnding, NetlifyConfig, PatchEntryFilter,
+    PatchMode, PathTemplate, ProjectInfo, RetryOn, DEFAULT_API_BASE_URL, DEFAULT_WRANGLER_PATH,
+    FILE_VALUE_PREFIX, MAX_VALUE_SIZE, MAX_VARS_PER_ENVIRONMENT, SECRET_FILE_MODE,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use owo_colors::{
+    OwoColorize,
+    Stream::{self, Stdout},
+};
+use reqwest::{
+    blocking::{Client, ClientBuilder},
+    header::{HeaderMap, HeaderValue},
+    redirect::Policy,
+};
+use serde::Serialize;
+use uuid::Uuid;
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 struct Cli {
+    #[clap(long, global = true, env = "NO_COLOR", help = "Disable colored output")]
+    no_color: bool,
+    // Only declared for parsing and `--help`. The file is loaded before parsing so that the
+    // credentials can be picked up as environment variables.
+    #[allow(dead_code)]
+    #[clap(
+        long,
+        global = true,
+        env = "CF_PAGES_CREDENTIALS_FILE",
+        help = "Path to a dotenv file containing CLOUDFLARE_ACCOUNT and CLOUDFLARE_TOKEN. \
+                Variables already set in the environment take precedence"
+    )]
+    credentials_file: Option<PathBuf>,
+    // Only declared for parsing and `--help`. The file is looked up with `config_path`, which
+    // `config` subcommands share.
+    #[allow(dead_code)]
+    #[clap(long, global = true, env = "CF_PAGES_CONFIG", help = config_help())]
+    config: Option<PathBuf>,
+    // Only declared for parsing and `--help`. The project name is read before parsing so that it
+    // can be picked up as `CF_PAGES_PROJECT`.
+    #[allow(dead_code)]
+    #[clap(
+        long,
+        global = true,
+        env = "CF_PAGES_PROJECT_FROM_WRANGLER",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_WRANGLER_PATH,
+        value_name = "PATH",
+        help = "Read the project name from the name field of a wrangler.toml file, making \
+                --project optional. The path defaults to wrangler.toml in the current directory"
+    )]
+    project_from_wrangler: Option<PathBuf>,
+    #[clap(
+        long,
+        global = true,
+        env = "CF_PAGES_QUIET",
+        help = "Do not report progress of long-running operations, nor notices such as \"No changes \
+                detected.\""
+    )]
+    quiet: bool,
     #[clap(subcommand)]
     command: Subcommands,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Environment {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Toml,
+    Text,
+    /// `[build.environment]` and `[context.deploy-preview.environment]` sections of a
+    /// `netlify.toml` file.
+    Netlify,
+    /// The production variables as a `.env` file, like `to-env-file` writes by default.
+    Dotenv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AuthMode {
+    /// `Authorization: Bearer` with an API token.
+    Bearer,
+    /// `X-Auth-Email` and `X-Auth-Key` with a global API key.
+    Legacy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ProjectsFormat {
+    Text,
+    /// All projects with all of their fields, including the variables, as a JSON array.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DeploymentsFormat {
+    Text,
+    Ndjson,
+}
+
+/// A deployment as printed by `list-deployments --format ndjson`.
+#[derive(Debug, Serialize)]
+struct DeploymentLine<'a> {
+    id: &'a str,
+    environment: Environment,
+    created_on: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum EnvFileFormat {
+    Dotenv,
+    /// Environment sections of a `netlify.toml` file.
+    Netlify,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum EnvironmentSelection {
     Production,
     Preview,
+    /// Both environments combined into one.
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum MergeOrder {
+    /// Production is applied first, so that preview values win.
+    ProductionFirst,
+    /// Preview is applied first, so that production values win.
+    PreviewFirst,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ToEnvFileFormat {
+    Dotenv,
+    /// `export KEY='VALUE'` lines for sourcing from POSIX shells.
+    Shell,
+    /// `KEY=VALUE` lines for a systemd `EnvironmentFile`.
+    Systemd,
+    /// A Kubernetes `Secret` manifest with base64-encoded values.
+    K8sSecret,
+    /// A Kubernetes `ConfigMap` manifest.
+    K8sConfigmap,
+    /// The variable list for `envsubst`, e.g. `${A} ${B}`.
+    EnvsubstTemplate,
+    /// `key=value` lines of a Java `.properties` file.
+    Properties,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DiffFormat {
+    Summary,
+    Unified,
+    /// Per-key changes including the old and new values.
+    Values,
+}
+
+/// Set from `--quiet` before running any subcommand.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Where credentials exported from files came from, by environment variable name. Reported by
+/// `doctor`.
+static CREDENTIAL_SOURCES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Reports the number of completed steps of a long-running operation on stderr. Only enabled when
+/// stderr is a terminal and `--quiet` is not set, so that output consumed by scripts is unaffected.
+struct Progress {
+    label: &'static str,
+    enabled: bool,
+}
+
+/// Everything `set-env-vars` is asked to do, hashed for `--state-file`.
+#[derive(Debug, Serialize)]
+struct DesiredState<'a> {
+    account: &'a str,
+    project: &'a str,
+    vars: &'a EnvVarsFile,
+    mode: Option<&'a str>,
+    ignored_keys: &'a [String],
+    only_additions: bool,
+    only_deletions: bool,
+}
+
+/// Machine-readable outcome of `set-env-vars`.
+#[derive(Debug, Serialize)]
+struct SetEnvVarsReport<'a> {
+    #[serde(flatten)]
+    summary: &'a ChangeSummary,
+    applied: bool,
 }
 
 #[derive(Debug, Subcommand)]
 enum Subcommands {
     #[clap(about = "Download environment variables into a local JSON file")]
     GetEnvVars(GetEnvVars),
+    #[clap(about = "Print the value of a single environment variable")]
+    GetVar(GetVar),
     #[clap(about = "Upload environment variables from a local JSON file")]
     SetEnvVars(SetEnvVars),
     #[clap(about = "Generate .env file for front-end development")]
     ToEnvFile(ToEnvFile),
+    #[clap(about = "Convert a .env file into a local JSON or TOML file")]
+    FromEnvFile(FromEnvFile),
+    #[clap(about = "Delete all variables whose names match a pattern")]
+    Prune(Prune),
+    #[clap(about = "Check a local file for problems without contacting Cloudflare")]
+    Validate(Validate),
+    #[clap(about = "List the Pages projects of the account")]
+    ListProjects(ListProjects),
+    #[clap(about = "List recent deployments of a project")]
+    ListDeployments(ListDeployments),
+    #[clap(about = "Show an overview of a project's environment variables")]
+    Info(Info),
+    #[clap(about = "Show the changes a local file would make without applying them")]
+    Diff(Diff),
+    #[clap(about = "Report differences between the production and preview variables of a project")]
+    CompareEnvironments(CompareEnvironments),
+    #[clap(about = "Read or write the config file")]
+    Config(ConfigCommand),
+    #[clap(about = "Check credentials and connectivity, for troubleshooting and bug reports")]
+    Doctor(Doctor),
+}
+
+#[derive(Debug, Parser)]
+pub struct ConfigCommand {
+    #[clap(subcommand)]
+    command: ConfigSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigSubcommands {
+    #[clap(about = "Set a field in the config file, creating the file if needed")]
+    Set(ConfigSet),
+    #[clap(about = "Print the config file, with the token masked")]
+    Show,
+    #[clap(about = "Print the location of the config file")]
+    Path,
+}
+
+#[derive(Debug, Parser)]
+pub struct ConfigSet {
+    #[clap(help = "Field to set")]
+    key: ConfigKey,
+    #[clap(help = "New value of the field")]
+    value: String,
 }
 
 #[derive(Debug, Parser)]
 pub struct GetEnvVars {
     #[clap(flatten)]
     credentials: CredentialsArgs,
-    #[clap(long, env = "CF_PAGES_PROJECT", help = "Name of the Pages project")]
-    project: String,
-    #[clap(long, env = "CF_PAGES_DEPLOYMENT", help = "Deployment ID")]
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        value_delimiter = ',',
+        required_unless_present = "projects_file",
+        help = "Name of the Pages project. Can be specified multiple times"
+    )]
+    project: Vec<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_PROJECTS_FILE",
+        help = "Path to a file listing project names, one per line"
+    )]
+    projects_file: Option<PathBuf>,
+    #[clap(
+        long,
+        env = "CF_PAGES_DEPLOYMENT",
+        help = "Deployment ID. Only supported for a single project. As a deployment belongs to a \
+                single environment, the other one is written as null"
+    )]
     deployment: Option<String>,
     #[clap(
+        long,
+        env = "CF_PAGES_FILL_MISSING",
+        requires = "deployment",
+        help = "With --deployment, write the environment the deployment does not belong to as an \
+                empty map instead of null"
+    )]
+    fill_missing: bool,
+    #[clap(
+        short,
         long,
         env = "CF_PAGES_OUTPUT",
-        help = "Path to save the JSON file. Prints to stdout if not provided"
+        help = "Path to save the file. Prints to stdout if not provided. May contain {project} \
+                and {environment} placeholders to write one file per project or environment"
     )]
-    output: Option<PathBuf>,
+    output: Option<PathTemplate>,
+    #[clap(
+        long,
+        env = "CF_PAGES_OUTPUT_DIR",
+        conflicts_with = "output",
+        help = "Directory to save one file per project into. Required for multiple projects"
+    )]
+    output_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        help = "Format of the file. Inferred from the output file extension if not provided. The \
+                text format is only available with --keys-only. The dotenv format only holds the \
+                production variables, with a warning if preview variables are left out"
+    )]
+    format: Option<OutputFormat>,
+    #[clap(
+        long,
+        env = "CF_PAGES_REDACT",
+        help = "Replace variable values with a placeholder, keeping only the keys"
+    )]
+    redact: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_KEYS_ONLY",
+        help = "Only emit the sorted list of variable names for each environment"
+    )]
+    keys_only: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_SHOW_TYPES",
+        requires = "keys_only",
+        help = "Annotate each key with [plain] or [secret]. Only supported with the text format"
+    )]
+    show_types: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_INCLUDE_SECRETS",
+        help = "Write the values of secrets as returned by the API. Secrets are written with empty \
+                values otherwise"
+    )]
+    include_secrets: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_SELECT",
+        value_delimiter = ',',
+        help = "Environments to include, e.g. production,preview. Others are left as null. All \
+                environments are included if not provided"
+    )]
+    select: Vec<Environment>,
+    #[clap(
+        long,
+        env = "CF_PAGES_NO_EOF_NEWLINE",
+        help = "Do not append a trailing newline after the JSON content"
+    )]
+    no_eof_newline: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_COMPACT",
+        help = "Write JSON without indentation or line breaks. Only affects the JSON format"
+    )]
+    compact: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_FAIL_FAST",
+        default_value_t = true,
+        action = clap::ArgAction::Set,
+        help = "Stop at the first project that fails. With false, the remaining projects are still \
+                processed and the failures are summarized at the end"
+    )]
+    fail_fast: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_COUNT",
+        conflicts_with_all = ["output", "output_dir", "format", "keys_only", "redact"],
+        help = "Only print the number of variables in each environment"
+    )]
+    count: bool,
+    #[clap(
+        long,
+        visible_alias = "chmod",
+        env = "CF_PAGES_OUTPUT_PERMISSIONS",
+        value_parser = parse_file_mode,
+        help = "Unix permissions of written files in octal, e.g. 600. Defaults to 600 with \
+                --include-secrets. Ignored on Windows"
+    )]
+    output_permissions: Option<u32>,
+    #[clap(
+        long,
+        env = "CF_PAGES_CREATE_MISSING_DIRS",
+        help = "Create missing parent directories of output files"
+    )]
+    create_missing_dirs: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ALSO_OUTPUT",
+        value_delimiter = ',',
+        conflicts_with_all = ["output_dir", "count"],
+        help = "Additional file to write from the same download, in the format given by its \
+                extension: .json, .toml, .txt (with --keys-only) or .env (production variables \
+                only, with a warning if preview variables are left out). Can be specified multiple \
+                times. Only supported for a single project"
+    )]
+    also_output: Vec<PathBuf>,
+    #[clap(
+        long,
+        visible_alias = "no-clobber",
+        env = "CF_PAGES_FAIL_IF_EXISTS",
+        help = "Fail instead of overwriting an existing output file"
+    )]
+    fail_if_exists: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORCE",
+        help = "Overwrite existing output files even if --fail-if-exists is set, e.g. from the \
+                environment"
+    )]
+    force: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct GetVar {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        help = "Name of the Pages project"
+    )]
+    project: String,
+    #[clap(
+        short,
+        long,
+        visible_alias = "env",
+        env = "CF_PAGES_ENVIRONMENT",
+        default_value = "production",
+        help = "Environment to read the variable from"
+    )]
+    environment: Environment,
+    #[clap(
+        long,
+        aliases = ["json-path", "select-key"],
+        help = "Parse the value as JSON and only print the field at this JSON pointer, e.g. \
+                /db/host"
+    )]
+    json_pointer: Option<String>,
+    #[clap(help = "Name of the variable")]
+    name: String,
 }
 
 #[derive(Debug, Parser)]
 pub struct SetEnvVars {
     #[clap(flatten)]
     credentials: CredentialsArgs,
-    #[clap(long, env = "CF_PAGES_PROJECT", help = "Name of the Pages project")]
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        help = "Name of the Pages project"
+    )]
     project: String,
     #[clap(
+        short,
         long,
         env = "CF_PAGES_FILE",
-        help = "Path to the file containing desired environment variables"
+        required_unless_present = "edit",
+        help = "Path or HTTP(S) URL to the file containing desired environment variables. Can be \
+                specified multiple times to overlay files, with later files overriding earlier ones. \
+                Use - to read from stdin"
     )]
-    file: PathBuf,
+    file: Vec<String>,
+    #[clap(
+        long,
+        conflicts_with_all = ["file", "json_lines", "inherit_from"],
+        help = "Open the current variables in $VISUAL or $EDITOR instead of reading a file, and \
+                apply the changes once the editor exits"
+    )]
+    edit: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        help = "Format of the files. Inferred from each file extension if not provided"
+    )]
+    format: Option<FileFormat>,
+    #[clap(
+        long,
+        env = "CF_PAGES_JSON_LINES",
+        conflicts_with = "format",
+        help = "Read the files as newline-delimited JSON objects of the form \
+                {\"env\": \"production\", \"key\": \"...\", \"value\": \"...\"}"
+    )]
+    json_lines: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_TRIM",
+        help = "Strip leading and trailing whitespace from values before uploading"
+    )]
+    trim: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_EXPAND_ENV",
+        help = "Expand ${VAR} references in values from the current environment. Use $$ for a \
+                literal $"
+    )]
+    expand_env: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ALLOW_MISSING",
+        requires = "expand_env",
+        help = "Expand references to undefined variables to empty strings instead of failing"
+    )]
+    allow_missing: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ALLOW_COMMAND_VALUES",
+        help = "Replace values of the form \"!cmd: <command>\" with the output of the command. \
+                Only use with trusted input files"
+    )]
+    allow_command_values: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ALLOW_FILE_VALUES",
+        help = "Replace values of the form \"@file:<path>\" with the content of the file. \
+                Relative paths are resolved against the directory of the input file"
+    )]
+    allow_file_values: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ENCODE_BASE64",
+        help = "Base64-encode all values before uploading"
+    )]
+    encode_base64: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ONLY",
+        help = "Only update the specified environment, leaving the other one untouched"
+    )]
+    only: Option<Environment>,
+    #[clap(
+        long,
+        env = "CF_PAGES_STRICT",
+        help = "Treat warnings about suspicious variables as errors"
+    )]
+    strict: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_MAX_VALUE_BYTES",
+        default_value_t = MAX_VALUE_SIZE,
+        help = "Fail before contacting Cloudflare if any value is larger than this many bytes"
+    )]
+    max_value_bytes: usize,
+    #[clap(
+        long,
+        env = "CF_PAGES_REPORT_JSON",
+        help = "Print a JSON report of the changes instead of human-readable messages"
+    )]
+    report_json: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_DRY_RUN_DIFF",
+        conflicts_with_all = ["create_if_missing", "report_json"],
+        help = "Print the changes with their old and new values without submitting them"
+    )]
+    dry_run_diff: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ONLY_CHANGED",
+        help = "Omit environments without changes from the summary"
+    )]
+    only_changed: bool,
+    #[clap(
+        long = "secret-key",
+        alias = "secret-keys",
+        env = "CF_PAGES_SECRET_KEYS",
+        value_delimiter = ',',
+        help = "Upload the variable with this name as a secret, regardless of how it is stored in \
+                the file. Can be specified multiple times"
+    )]
+    secret_keys: Vec<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_INHERIT_FROM",
+        help = "Compute the other environment as the variables of this environment in the file, \
+                overridden by the other environment's own section. Set a variable to null in that \
+                section to delete it there instead of inheriting it"
+    )]
+    inherit_from: Option<Environment>,
+    #[clap(
+        long,
+        env = "CF_PAGES_MAX_VARS_PER_REQUEST",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Split the update into multiple requests with at most this many variables each"
+    )]
+    max_vars_per_request: Option<u64>,
+    #[clap(
+        long,
+        env = "CF_PAGES_MODE",
+        default_value = "replace",
+        help = "How variables missing from an environment in the file are treated: replace \
+                deletes them, while merge leaves them untouched. Variables set to null in the \
+                file are deleted in both modes. Environments missing from the file are never \
+                touched"
+    )]
+    mode: PatchMode,
+    #[clap(
+        long,
+        env = "CF_PAGES_MAX_RETRIES",
+        default_value = "0",
+        help = "Number of times to retry failed requests, as selected by --retry-on"
+    )]
+    max_retries: usize,
+    #[clap(
+        long,
+        env = "CF_PAGES_RETRY_ON",
+        help = "Comma-separated failures to retry: timeout, connect, 5xx and 429. Defaults to \
+                connect for update requests, which is the only failure certain to have changed \
+                nothing, and to all of them for reading the remote variables"
+    )]
+    retry_on: Option<RetryOn>,
+    #[clap(
+        long,
+        env = "CF_PAGES_RETRY_BUDGET",
+        value_parser = parse_duration,
+        help = "Maximum total time to spend on the update including retries, e.g. 5m. Failures \
+                are no longer retried once waiting for the next retry would exceed it"
+    )]
+    retry_budget: Option<Duration>,
+    #[clap(
+        long,
+        env = "CF_PAGES_RECOMPUTE_ON_RETRY",
+        default_value_t = true,
+        action = clap::ArgAction::Set,
+        help = "Fetch the remote variables again and recompute the changes before each retry"
+    )]
+    recompute_on_retry: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_CREATE_IF_MISSING",
+        help = "Create the project if it does not exist yet"
+    )]
+    create_if_missing: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_PRODUCTION_BRANCH",
+        default_value = "main",
+        help = "Production branch of projects created with --create-if-missing"
+    )]
+    production_branch: String,
+    #[clap(
+        long,
+        env = "CF_PAGES_REQUIRE_KEYS",
+        value_delimiter = ',',
+        help = "Keys that must be present in both environments after the update. Nothing is \
+                submitted if any of them would be missing"
+    )]
+    require_keys: Vec<String>,
+    #[clap(
+        long = "ignore-key",
+        visible_alias = "ignore-keys",
+        env = "CF_PAGES_IGNORE_KEYS",
+        value_delimiter = ',',
+        help = "Never add, change or delete the variable with this name, regardless of the file. \
+                Can be specified multiple times"
+    )]
+    ignore_keys: Vec<String>,
+    // Only accepted to explain why it is not supported. Deliberately not read from
+    // `CF_PAGES_DEPLOYMENT`, which may be set for `get-env-vars`.
+    #[clap(long, hide = true)]
+    deployment: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_ONLY_ADDITIONS",
+        conflicts_with = "only_deletions",
+        help = "Only add and change variables, leaving deletions for a later run"
+    )]
+    only_additions: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ONLY_DELETIONS",
+        help = "Only delete variables, leaving additions and changes for a later run"
+    )]
+    only_deletions: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_STATE_FILE",
+        conflicts_with_all = ["edit", "dry_run_diff"],
+        help = "File to record a hash of the desired state in after each successful update"
+    )]
+    state_file: Option<PathBuf>,
+    #[clap(
+        long,
+        env = "CF_PAGES_SKIP_IF_UNCHANGED",
+        requires = "state_file",
+        help = "Exit without contacting Cloudflare if the desired state matches the hash in \
+                --state-file. Changes made remotely since the last run are not detected"
+    )]
+    skip_if_unchanged: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct ToEnvFile {
+    #[clap(flatten)]
+    client: ClientArgs,
     #[clap(
+        short,
         long,
+        visible_alias = "env",
         env = "CF_PAGES_ENVIRONMENT",
         default_value = "production",
-        help = "Environment to export"
+        help = "Environment to export. both combines the two environments into one file"
     )]
-    environment: Environment,
+    environment: EnvironmentSelection,
+    #[clap(
+        long,
+        env = "CF_PAGES_MERGE_ORDER",
+        default_value = "production-first",
+        help = "Order in which environments are combined with --environment both. Values of the \
+                environment applied last win"
+    )]
+    merge_order: MergeOrder,
     #[clap(
         long,
         env = "CF_PAGES_EMPTY",
@@ -75,298 +743,2256 @@ pub struct ToEnvFile {
     empty: bool,
     #[clap(
         long,
-        env = "CF_PAGES_OUTPUT",
-        help = "Path to save the .env file. Prints to stdout if not provided"
+        env = "CF_PAGES_DECODE_BASE64",
+        help = "Base64-decode all values before exporting"
     )]
-    output: Option<PathBuf>,
-    #[clap(help = "Path to the JSON file containing environment variables")]
-    file: String,
-}
+    decode_base64: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_TRIM",
+        help = "Strip leading and trailing whitespace from values before exporting"
+    )]
+    trim: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_ENV_FILE_QUOTE",
+        default_value = "auto",
+        help = "When to wrap values in double quotes. Values are always single-quoted in the shell \
+                format"
+    )]
+    env_file_quote: EnvFileQuote,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        default_value = "dotenv",
+        help = "Format of the output. shell writes export KEY='VALUE' lines for sourcing from \
+                bash or zsh, systemd writes a file for the EnvironmentFile setting of systemd units, \
+                k8s-secret and k8s-configmap write a Kubernetes manifest, and \
+                envsubst-template writes the variable list to pass to envsubst, and properties \
+                writes a Java .properties file"
+    )]
+    format: ToEnvFileFormat,
+    #[clap(
+        long,
+        env = "CF_PAGES_TRANSFORM_KEYS",
+        help = "Convert variable names to upper or lower case. Fails if two names become the same"
+    )]
+    transform_keys: Option<KeyTransform>,
+    #[clap(
+        long,
+        env = "CF_PAGES_K8S_NAME",
+        required_if_eq_any = [("format", "k8s-secret"), ("format", "k8s-configmap")],
+        help = "Name of the Kubernetes object. Required for the Kubernetes formats"
+    )]
+    name: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_K8S_NAMESPACE",
+        help = "Namespace of the Kubernetes object. Omitted from the manifest if not provided"
+    )]
+    namespace: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_FAIL_ON_EMPTY",
+        help = "Fail if the selected environment has no variables"
+    )]
+    fail_on_empty: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_LINE_ENDING",
+        default_value = "lf",
+        help = "Line separator of the .env file"
+    )]
+    line_ending: LineEnding,
+    #[clap(
+        long,
+        env = "CF_PAGES_NO_TRAILING_NEWLINE",
+        help = "Omit the line separator after the last line"
+    )]
+    no_trailing_newline: bool,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_OUTPUT",
+        help = "Path to save the .env file. Prints to stdout if not provided"
+    )]
+    output: Option<PathBuf>,
+    #[clap(
+        long,
+        visible_alias = "chmod",
+        env = "CF_PAGES_OUTPUT_PERMISSIONS",
+        value_parser = parse_file_mode,
+        help = "Unix permissions of the written file in octal, e.g. 600. Defaults to 600 when the \
+                environment contains secrets. Ignored on Windows"
+    )]
+    output_permissions: Option<u32>,
+    #[clap(
+        long,
+        env = "CF_PAGES_CREATE_MISSING_DIRS",
+        help = "Create missing parent directories of the output file"
+    )]
+    create_missing_dirs: bool,
+    #[clap(
+        long,
+        visible_alias = "no-clobber",
+        env = "CF_PAGES_FAIL_IF_EXISTS",
+        help = "Fail instead of overwriting an existing output file"
+    )]
+    fail_if_exists: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORCE",
+        help = "Overwrite existing output files even if --fail-if-exists is set, e.g. from the \
+                environment"
+    )]
+    force: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_COMMENTS_FILE",
+        help = "Path to a comments file written by from-env-file. Comments are emitted above the \
+                corresponding variables"
+    )]
+    comments_file: Option<PathBuf>,
+    #[clap(help = "Path or HTTP(S) URL to the JSON or TOML file containing environment variables")]
+    file: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct FromEnvFile {
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        visible_alias = "env",
+        env = "CF_PAGES_ENVIRONMENT",
+        default_value = "production",
+        help = "Environment the variables belong to. The other environment is left as null"
+    )]
+    environment: Environment,
+    #[clap(
+        long,
+        env = "CF_PAGES_INPUT_FORMAT",
+        help = "Format of the input file. netlify reads the variables Netlify would use for the \
+                environment from a netlify.toml file. Inferred from the input file extension if \
+                not provided"
+    )]
+    input_format: Option<EnvFileFormat>,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        help = "Format of the file. Inferred from the output file extension if not provided"
+    )]
+    format: Option<FileFormat>,
+    #[clap(
+        long,
+        env = "CF_PAGES_STRICT",
+        help = "Fail instead of warning when a key is assigned more than once"
+    )]
+    strict: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_TRANSFORM_KEYS",
+        help = "Convert variable names to upper or lower case. Fails if two names become the same"
+    )]
+    transform_keys: Option<KeyTransform>,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_OUTPUT",
+        help = "Path to save the file. Prints to stdout if not provided"
+    )]
+    output: Option<PathBuf>,
+    #[clap(
+        long,
+        env = "CF_PAGES_COMMENTS_FILE",
+        help = "Path to save the comments found above each variable into, for restoring them \
+                with to-env-file"
+    )]
+    comments_file: Option<PathBuf>,
+    #[clap(help = "Path or HTTP(S) URL to the .env or netlify.toml file")]
+    file: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct Prune {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        help = "Name of the Pages project"
+    )]
+    project: String,
+    #[clap(
+        long,
+        env = "CF_PAGES_PATTERN",
+        help = "Glob pattern matching the names of variables to delete, e.g. OLD_*"
+    )]
+    pattern: String,
+    #[clap(
+        long,
+        env = "CF_PAGES_ONLY",
+        help = "Only delete variables from this environment. Both environments are affected if not \
+                provided"
+    )]
+    only: Option<Environment>,
+    #[clap(
+        long,
+        env = "CF_PAGES_YES",
+        help = "Delete without asking for confirmation"
+    )]
+    yes: bool,
+    #[clap(flatten)]
+    dry_run: DryRunArgs,
+}
+
+#[derive(Debug, Parser)]
+pub struct Validate {
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        help = "Format of the file. Inferred from the file extension if not provided"
+    )]
+    format: Option<FileFormat>,
+    #[clap(
+        long,
+        env = "CF_PAGES_REQUIRE_KEYS",
+        value_delimiter = ',',
+        help = "Keys that must be present in every environment in the file"
+    )]
+    require_keys: Vec<String>,
+    #[clap(help = "Path to the JSON or TOML file containing environment variables")]
+    file: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct ListProjects {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        default_value = "text",
+        help = "Output format. json prints the full projects, including their variables"
+    )]
+    format: ProjectsFormat,
+    #[clap(
+        long,
+        env = "CF_PAGES_FIELDS",
+        value_delimiter = ',',
+        help = "Only include these fields of each project in the JSON output, e.g. id,name"
+    )]
+    fields: Vec<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct ListDeployments {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        help = "Name of the Pages project"
+    )]
+    project: String,
+    #[clap(
+        long,
+        env = "CF_PAGES_SINCE",
+        value_parser = parse_duration,
+        help = "Only show deployments created within this duration, e.g. 24h or 7d. Supported \
+                units: s, m, h, d, w. Only the latest page of deployments is shown if not provided"
+    )]
+    since: Option<Duration>,
+    #[clap(
+        short,
+        long,
+        visible_alias = "env",
+        env = "CF_PAGES_ENVIRONMENT",
+        help = "Only show deployments of this environment"
+    )]
+    environment: Option<Environment>,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        default_value = "text",
+        help = "Output format. ndjson prints one JSON object per deployment and line"
+    )]
+    format: DeploymentsFormat,
+    #[clap(
+        long,
+        env = "CF_PAGES_LIMIT",
+        help = "Stop after this many deployments, fetching more pages if needed"
+    )]
+    limit: Option<usize>,
+    #[clap(
+        long,
+        env = "CF_PAGES_PER_PAGE",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Number of deployments to fetch per request"
+    )]
+    per_page: Option<u64>,
+    #[clap(
+        long,
+        env = "CF_PAGES_START_PAGE",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Page to start listing from, e.g. to resume an interrupted listing. Pages start at \
+                1, with a size set by --per-page"
+    )]
+    start_page: Option<u64>,
+}
+
+#[derive(Debug, Parser)]
+pub struct Info {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        help = "Name of the Pages project"
+    )]
+    project: String,
+    #[clap(long, env = "CF_PAGES_JSON", help = "Print the overview as JSON")]
+    json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CompareEnvironments {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        help = "Name of the Pages project"
+    )]
+    project: String,
+    #[clap(
+        long,
+        env = "CF_PAGES_KEYS_ONLY",
+        help = "Only list the keys with different values, without printing the values"
+    )]
+    keys_only: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct Diff {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_PROJECT",
+        required_unless_present = "base",
+        help = "Name of the Pages project"
+    )]
+    project: Option<String>,
+    #[clap(
+        short,
+        long,
+        env = "CF_PAGES_FILE",
+        help = "Path or HTTP(S) URL to the file containing desired environment variables"
+    )]
+    file: String,
+    #[clap(
+        long,
+        env = "CF_PAGES_DIFF_BASE",
+        help = "Path or HTTP(S) URL to a file to diff against instead of the remote variables. \
+                No credentials are needed then"
+    )]
+    base: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_FORMAT",
+        help = "Format of the file. Inferred from the file extension if not provided"
+    )]
+    format: Option<FileFormat>,
+    #[clap(
+        long,
+        env = "CF_PAGES_DIFF_FORMAT",
+        default_value = "summary",
+        help = "How to render the changes"
+    )]
+    diff_format: DiffFormat,
+    #[clap(
+        long,
+        env = "CF_PAGES_MODE",
+        default_value = "replace",
+        help = "How variables missing from an environment in the file are treated: replace \
+                deletes them, while merge leaves them untouched. Variables set to null in the \
+                file are deleted in both modes. Environments missing from the file are never \
+                touched"
+    )]
+    mode: PatchMode,
+    #[clap(
+        long,
+        env = "CF_PAGES_ONLY_CHANGED",
+        help = "Omit environments without changes from the summary"
+    )]
+    only_changed: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_EXIT_CODE",
+        help = "Exit with status 1 if there are changes, and 0 otherwise"
+    )]
+    exit_code: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_DIFF_ONLY_KEYS",
+        help = "Only report keys that would be added or removed, ignoring value changes. Values \
+                are never printed"
+    )]
+    diff_only_keys: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct Doctor {
+    #[clap(flatten)]
+    credentials: CredentialsArgs,
+    #[clap(flatten)]
+    client: ClientArgs,
+}
+
+#[derive(Debug, Clone, Parser)]
+struct CredentialsArgs {
+    #[clap(
+        long,
+        env = "CLOUDFLARE_ACCOUNT",
+        help = "Cloudflare account ID. Discovered from the token if not provided, as long as the \
+                token only has access to a single account"
+    )]
+    account: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_ACCOUNT_ENV",
+        help = "Name of an environment variable to read the account ID from instead. Takes \
+                precedence over --account"
+    )]
+    account_env: Option<String>,
+    #[clap(
+        long,
+        env = "CLOUDFLARE_TOKEN",
+        help = "Cloudflare access token. Required unless --token-env is given"
+    )]
+    token: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_TOKEN_ENV",
+        help = "Name of an environment variable to read the access token from instead. Takes \
+                precedence over --token"
+    )]
+    token_env: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_AUTH_MODE",
+        default_value = "bearer",
+        help = "How to authenticate. bearer sends the token as an API token, while legacy sends \
+                it as a global API key along with --email"
+    )]
+    auth_mode: AuthMode,
+    #[clap(
+        long,
+        env = "CLOUDFLARE_EMAIL",
+        help = "Email of the Cloudflare account, required by --auth-mode legacy"
+    )]
+    email: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_CHECK_SCOPES",
+        help = "Check that the token has the Pages Read and Pages Write permissions before doing \
+                anything else, warning about missing ones. Costs two extra requests"
+    )]
+    check_scopes: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_API_BASE_URL",
+        default_value = DEFAULT_API_BASE_URL,
+        help = "Base URL of the Cloudflare API"
+    )]
+    api_base_url: String,
+}
+
+/// Shared by commands deleting or otherwise destroying variables.
+#[derive(Debug, Clone, Parser)]
+struct DryRunArgs {
+    #[clap(
+        long,
+        env = "CF_PAGES_DRY_RUN",
+        help = "Print the changes that would be made without applying them"
+    )]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Parser)]
+struct ClientArgs {
+    #[clap(
+        long,
+        hide = true,
+        env = "CF_PAGES_INSECURE",
+        help = "Skip TLS certificate verification. Only meant for testing against local mock \
+                servers and must never be used with the real API"
+    )]
+    insecure: bool,
+    #[clap(
+        long,
+        env = "CF_PAGES_TIMEOUT",
+        default_value = "10",
+        help = "Timeout in seconds for each request as a whole"
+    )]
+    timeout: u64,
+    #[clap(
+        long,
+        env = "CF_PAGES_CONNECT_TIMEOUT",
+        default_value = "5",
+        help = "Timeout in seconds for establishing connections"
+    )]
+    connect_timeout: u64,
+    #[clap(
+        long,
+        env = "CF_PAGES_REQUEST_ID",
+        help = "ID sent in the X-Request-Id header of all requests. Randomly generated if not \
+                provided"
+    )]
+    request_id: Option<String>,
+    #[clap(
+        long,
+        env = "CF_PAGES_FOLLOW_REDIRECTS",
+        help = "Follow redirects to other hosts. Only redirects within the same host are followed \
+                by default, so that the access token is never sent to an unexpected host"
+    )]
+    follow_redirects: bool,
+}
+
+impl GetEnvVars {
+    fn run(self) -> Result<()> {
+        let mut projects = self.project.clone();
+        if let Some(projects_file) = &self.projects_file {
+            projects.extend(
+                std::fs::read_to_string(projects_file)?
+                    .lines()
+                    .map(|line| line.trim())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| line.to_owned()),
+            );
+        }
+
+        let client = self
+            .credentials
+            .clone()
+            .into_client(self.client.build_client()?)?;
+
+        if self.count {
+            return self.print_counts(&client, &projects);
+        }
+
+        if !self.also_output.is_empty()
+            && (projects.len() > 1
+                || self
+                    .output
+                    .as_ref()
+                    .is_some_and(|output| output.is_templated()))
+        {
+            anyhow::bail!(
+                "--also-output is only supported for a single project without placeholders in \
+                --output"
+            );
+        }
+
+        match (&self.output_dir, &self.output, projects.as_slice()) {
+            (None, Some(output), projects) if output.is_templated() => {
+                if projects.len() > 1 && !output.has_project() {
+                    anyhow::bail!("--output must contain {{project}} for multiple projects");
+                }
+
+                self.download_all(
+                    &client,
+                    projects,
+                    output.has_environment(),
+                    |project, env| output.render(project, env),
+                )
+            }
+            (None, output, [project]) => {
+                let env_vars = self.fetch(&client, project)?;
+                let content = self.render(env_vars.clone())?;
+                // Rendered upfront, so that nothing is written if any of the formats fails
+                let extra_outputs = self
+                    .also_output
+                    .iter()
+                    .map(|path| {
+                        let format = OutputFormat::from_path(path);
+                        Ok((path, self.render_as(env_vars.clone(), format)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                self.write_output(
+                    output.as_ref().map(|output| Path::new(output.as_str())),
+                    &content,
+                )?;
+                for (path, content) in extra_outputs.iter() {
+                    self.write_output(Some(path), content)?;
+                }
+                Ok(())
+            }
+            (None, _, _) => anyhow::bail!("--output-dir is required for multiple projects"),
+            (Some(output_dir), _, projects) => {
+                let extension = match self.format() {
+                    OutputFormat::Json => "json",
+                    OutputFormat::Toml | OutputFormat::Netlify => "toml",
+                    OutputFormat::Text => "txt",
+                    OutputFormat::Dotenv => "env",
+                };
+
+                self.download_all(&client, projects, false, |project, _| {
+                    output_dir.join(format!("{project}.{extension}"))
+                })
+            }
+        }
+    }
+
+    /// Downloads the variables of each project into the file given by `path_for`, continuing past
+    /// failures. With `split_environments`, each environment is written into its own file.
+    fn download_all<F>(
+        &self,
+        client: &CloudflareClient,
+        projects: &[String],
+        split_environments: bool,
+        path_for: F,
+    ) -> Result<()>
+    where
+        F: Fn(&str, Option<Environment>) -> PathBuf,
+    {
+        if self.deployment.is_some() && projects.len() > 1 {
+            anyhow::bail!("--deployment is only supported for a single project");
+        }
+
+        self.for_each_project(projects, "Downloaded projects", |project| {
+            self.fetch(client, project).and_then(|env_vars| {
+                if !split_environments {
+                    let content = self.render(env_vars)?;
+                    return self.write_output(Some(&path_for(project, None)), &content);
+                }
+
+                for (environment, env) in env_vars.environments() {
+                    let single_env_vars = match environment {
+                        Environment::Production => EnvVarsFile {
+                            production: Some(env.to_owned()),
+                            preview: None,
+                        },
+                        Environment::Preview => EnvVarsFile {
+                            production: None,
+                            preview: Some(env.to_owned()),
+                        },
+                    };
+                    let content = self.render(single_env_vars)?;
+                    self.write_output(Some(&path_for(project, Some(environment))), &content)?;
+                }
+                Ok(())
+            })
+        })
+    }
+
+    /// Downloads the variables of a project or of the selected deployment. A deployment only has
+    /// the variables of its own environment, leaving the other one `null` unless `--fill-missing`
+    /// is set.
+    fn fetch(&self, client: &CloudflareClient, project: &str) -> Result<EnvVarsFile> {
+        let mut env_vars = fetch_env_vars(client, project, self.deployment.as_deref())?;
+        if let Some(deployment) = &self.deployment {
+            if self.fill_missing {
+                env_vars.fill_missing();
+            } else if let Some((environment, _)) = env_vars.environments().next() {
+                let other = match environment {
+                    Environment::Production => Environment::Preview,
+                    Environment::Preview => Environment::Production,
+                };
+                eprintln!(
+                    "Note: deployment {deployment} belongs to the {environment} environment, so \
+                    {other} is written as null. Pass --fill-missing to write an empty map instead"
+                );
+            }
+        }
+        Ok(env_vars)
+    }
+
+    /// Runs `f` for each project. Unless `--fail-fast false` is set, the first failure aborts
+    /// the run. Otherwise all projects are processed, and the failures are listed at the end.
+    fn for_each_project<F>(&self, projects: &[String], label: &'static str, mut f: F) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<()>,
+    {
+        let progress = Progress::new(label);
+        let mut failures = vec![];
+        for (ind, project) in projects.iter().enumerate() {
+            if let Err(err) = f(project) {
+                if self.fail_fast {
+                    return Err(err.context(format!("failed to process project {project}")));
+                }
+                eprintln!("Failed to process project {project}: {err:#}");
+                failures.push((project, err));
+            }
+            progress.update(ind + 1, projects.len());
+        }
+
+        if !failures.is_empty() {
+            eprintln!("Failed projects:");
+            for (project, err) in failures.iter() {
+                let category = CfPagesError::find(err).map_or("other", |err| err.category());
+                eprintln!("  {project} ({category})");
+            }
+            anyhow::bail!(
+                "failed to process {} of {} projects",
+                failures.len(),
+                projects.len()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Prints the number of variables per environment, prefixed with the project name when
+    /// there are multiple projects.
+    fn print_counts(&self, client: &CloudflareClient, projects: &[String]) -> Result<()> {
+        if self.deployment.is_some() && projects.len() > 1 {
+            anyhow::bail!("--deployment is only supported for a single project");
+        }
+
+        self.for_each_project(projects, "Counted projects", |project| {
+            let mut env_vars = self.fetch(client, project)?;
+            self.apply_select(&mut env_vars);
+
+            let counts = env_vars
+                .environments()
+                .map(|(environment, env)| {
+                    let count = env.values().filter(|var| var.is_some()).count();
+                    format!("{environment}: {count}")
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            if projects.len() > 1 {
+                println!("{project}: {counts}");
+            } else {
+                println!("{counts}");
+            }
+            Ok(())
+        })
+    }
+
+    fn apply_select(&self, env_vars: &mut EnvVarsFile) {
+        if !self.select.is_empty() {
+            if !self.select.contains(&Environment::Production) {
+                env_vars.production = None;
+            }
+            if !self.select.contains(&Environment::Preview) {
+                env_vars.preview = None;
+            }
+        }
+    }
+
+    fn format(&self) -> OutputFormat {
+        match (self.format, &self.output) {
+            (Some(format), _) => format,
+            (None, Some(output)) => OutputFormat::from_path(Path::new(output.as_str())),
+            (None, None) => OutputFormat::Json,
+        }
+    }
+
+    fn render(&self, env_vars: EnvVarsFile) -> Result<String> {
+        self.render_as(env_vars, self.format())
+    }
+
+    fn render_as(&self, mut env_vars: EnvVarsFile, format: OutputFormat) -> Result<String> {
+        self.apply_select(&mut env_vars);
+        if !self.include_secrets {
+            env_vars.strip_secret_values();
+        }
+        if self.redact {
+            env_vars.redact();
+        }
+
+        if self.show_types && format != OutputFormat::Text {
+            anyhow::bail!("--show-types is only supported with the text format");
+        }
+
+        Ok(if self.show_types {
+            format_typed_keys_as_text(&env_vars)
+        } else if self.keys_only {
+            let keys = EnvKeysFile::from(&env_vars);
+            match format {
+                OutputFormat::Json => self.serialize_json(&keys)?,
+                OutputFormat::Toml => FileFormat::Toml.serialize(&keys)?,
+                OutputFormat::Text => format_keys_as_text(&keys),
+                OutputFormat::Netlify => {
+                    anyhow::bail!("the netlify format is not supported with --keys-only")
+                }
+                OutputFormat::Dotenv => {
+                    anyhow::bail!("the dotenv format is not supported with --keys-only")
+                }
+            }
+        } else {
+            match format {
+                OutputFormat::Json => self.serialize_json(&env_vars)?,
+                OutputFormat::Toml => FileFormat::Toml.serialize(&env_vars)?,
+                OutputFormat::Netlify => {
+                    FileFormat::Toml.serialize(&NetlifyConfig::from(&env_vars))?
+                }
+                OutputFormat::Text => {
+                    anyhow::bail!("the text format is only supported with --keys-only")
+                }
+                OutputFormat::Dotenv => {
+                    let preview_count = env_vars.preview.as_ref().map_or(0, |env| env.len());
+                    if preview_count > 0 {
+                        eprintln!(
+                            "Warning: the dotenv format only holds production variables, leaving \
+                            out {preview_count} preview variable(s). Use to-env-file --environment \
+                            preview for those"
+                        );
+                    }
+                    env_vars
+                        .production
+                        .iter()
+                        .flatten()
+                        .filter_map(|(key, var)| {
+                            var.as_ref()
+                                .map(|var| EnvFileQuote::Auto.format_line(key, &var.value))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+        })
+    }
+
+    fn serialize_json<T>(&self, value: &T) -> Result<String>
+    where
+        T: Serialize,
+    {
+        if self.compact {
+            Ok(serde_json::to_string(value)?)
+        } else {
+            FileFormat::Json.serialize(value)
+        }
+    }
+
+    fn write_output(&self, output: Option<&Path>, content: &str) -> Result<()> {
+        if let Some(output) = output {
+            // Secret values are only written with --include-secrets
+            let mode = self.output_permissions.or_else(|| {
+                (self.include_secrets && !self.redact && !self.keys_only)
+                    .then_some(SECRET_FILE_MODE)
+            });
+            ensure_parent_dir(output, self.create_missing_dirs)?;
+            let mut dump_file =
+                create_output_file(output, mode, !self.fail_if_exists || self.force)?;
+            dump_file.write_all(content.as_bytes())?;
+
+            // EOF line for Unix platforms
+            if !self.no_eof_newline {
+                writeln!(&mut dump_file)?;
+            }
+
+            print_notice(&format!(
+                "Environment variables written to: {}",
+                output.to_string_lossy()
+            ));
+        } else if self.no_eof_newline {
+            print!("{content}");
+        } else {
+            println!("{content}");
+        }
+
+        Ok(())
+    }
+}
+
+impl GetVar {
+    fn run(self) -> Result<()> {
+        let client = self.credentials.into_client(self.client.build_client()?)?;
+
+        let env_vars = fetch_env_vars(&client, &self.project, None)?;
+        let env = match self.environment {
+            Environment::Production => env_vars.production,
+            Environment::Preview => env_vars.preview,
+        };
+        let var = match env.and_then(|mut env| env.remove(&self.name)).flatten() {
+            Some(var) => var,
+            None => anyhow::bail!(
+                "variable {} not found in the {} environment",
+                self.name,
+                self.environment
+            ),
+        };
+        if var.secret {
+            anyhow::bail!(
+                "variable {} is a secret, whose value cannot be retrieved",
+                self.name
+            );
+        }
+
+        match &self.json_pointer {
+            Some(pointer) => println!(
+                "{}",
+                extract_json_pointer(&var.value, pointer)
+                    .with_context(|| format!("unable to extract field from {}", self.name))?
+            ),
+            None => println!("{}", var.value),
+        }
+
+        Ok(())
+    }
+}
+
+impl SetEnvVars {
+    fn run(self) -> Result<()> {
+        if let Some(deployment) = &self.deployment {
+            anyhow::bail!(
+                "cannot set variables of deployment {deployment}: the variables of a deployment \
+                are fixed once it is created. Only the project configuration can be changed, \
+                which applies to new deployments. Remove --deployment to update the project"
+            );
+        }
+
+        let http_client = self.client.build_client()?;
+
+        let mut files = vec![];
+        for file in self.file.iter() {
+            if is_glob(file) && !is_url(file) {
+                files.extend(
+                    expand_path_glob(file)?
+                        .into_iter()
+                        .map(|path| path.to_string_lossy().into_owned()),
+                );
+            } else {
+                files.push(file.to_owned());
+            }
+        }
+
+        let mut new_vars = EnvVarsFile::default();
+        if self.edit {
+            let client = self.credentials.clone().into_client(http_client.clone())?;
+            let format = self.format.unwrap_or(FileFormat::Json);
+            match edit_env_vars(&client, &self.project, format)? {
+                Some(vars) => new_vars = vars,
+                None => {
+                    print_notice("File left unchanged. Not submitting patch.");
+                    return Ok(());
+                }
+            }
+        }
+        for file in files.iter() {
+            let content = read_input_file(&http_client, file)?;
+            let mut vars: EnvVarsFile = if self.json_lines {
+                parse_json_lines(&content)
+            } else {
+                let format = self.format.unwrap_or_else(|| FileFormat::from_path(file));
+                format.deserialize(&content)
+            }
+            .with_context(|| format!("invalid syntax in {file}"))?;
+            self.resolve_file_values(&mut vars, file)?;
+            new_vars.merge(vars);
+        }
+        if let Some(source) = self.inherit_from {
+            new_vars.inherit_from(source);
+        }
+        // Command output and file content are used verbatim, so they are neither trimmed nor
+        // expanded
+        let mut trimmed_count = 0;
+        for (key, value) in new_vars.iter_mut() {
+            if command_value(value).is_some() && !self.allow_command_values {
+                if self.strict {
+                    anyhow::bail!(
+                        "value of {key} is a command. Pass --allow-command-values to run it"
+                    );
+                }
+                eprintln!(
+                    "Warning: value of {key} looks like a command but is uploaded literally. \
+                    Pass --allow-command-values to run it"
+                );
+            }
+            *value = resolve_value(
+                value,
+                self.allow_command_values,
+                self.allow_file_values,
+                |value| {
+                    let mut value = value.to_owned();
+                    if self.trim {
+                        trimmed_count += trim_values([&mut value]);
+                    }
+                    if self.expand_env {
+                        value = expand_env_refs(
+                            &value,
+                            |name| std::env::var(name).ok(),
+                            self.allow_missing,
+                        )?;
+                    }
+                    Ok(value)
+                },
+            )
+            .with_context(|| format!("unable to resolve value of variable {key}"))?;
+        }
+        if self.encode_base64 {
+            new_vars
+                .values_mut()
+                .for_each(|value| *value = BASE64.encode(value.as_bytes()));
+        }
+
+        for key in new_vars.mark_secret(&self.secret_keys) {
+            eprintln!("Warning: secret key {key} not found in the file");
+        }
+
+        match self.only {
+            Some(Environment::Production) => new_vars.preview = None,
+            Some(Environment::Preview) => new_vars.production = None,
+            None => {}
+        }
+
+        let oversized = find_oversized_values(&new_vars, self.max_value_bytes);
+        if !oversized.is_empty() {
+            for (environment, key, size) in oversized.iter() {
+                eprintln!("{environment}: value of {key} is {size} bytes");
+            }
+            anyhow::bail!(
+                "{} value(s) exceed the limit of {} bytes set by --max-value-bytes",
+                oversized.len(),
+                self.max_value_bytes
+            );
+        }
+
+        for (environment, env) in new_vars.environments() {
+            for collision in find_case_collisions(env) {
+                let message = format!(
+                    "keys differing only by case in {}: {}",
+                    environment,
+                    collision.join(", ")
+                );
+                if self.strict {
+                    anyhow::bail!(message);
+                }
+                eprintln!("Warning: {message}");
+            }
+        }
+
+        let mut api_client = None;
+        let desired_hash = match &self.state_file {
+            Some(state_file) => {
+                // Part of the state so that projects of the same name in different accounts never
+                // share a hash. Discovering it from the token is the only request made before the
+                // comparison.
+                let account = match self.credentials.configured_account()? {
+                    Some(account) => account,
+                    None => {
+                        let client = self.credentials.clone().into_client(http_client.clone())?;
+                        let account = client.account().to_owned();
+                        api_client = Some(client);
+                        account
+                    }
+                };
+                let mode = self.mode.to_possible_value();
+                let hash = state_hash(&DesiredState {
+                    account: &account,
+                    project: &self.project,
+                    vars: &new_vars,
+                    mode: mode.as_ref().map(|mode| mode.get_name()),
+                    ignored_keys: &self.ignore_keys,
+                    only_additions: self.only_additions,
+                    only_deletions: self.only_deletions,
+                })?;
+                if self.skip_if_unchanged
+                    && read_state_hash(state_file)?.as_deref() == Some(hash.as_str())
+                {
+                    print_notice(
+                        "Desired state unchanged since the last run. Not submitting patch.",
+                    );
+                    return Ok(());
+                }
+                Some(hash)
+            }
+            None => None,
+        };
+
+        // Only resolved now, so that problems with the file are reported before any request is
+        // sent to Cloudflare
+        let client = CachedApi::new(match api_client {
+            Some(api_client) => api_client,
+            None => self.credentials.into_client(http_client)?,
+        });
+
+        if self.create_if_missing
+            && ensure_project(&client, &self.project, &self.production_branch)?
+            && !self.report_json
+        {
+            print_notice(&format!("Created project {}", self.project));
+        }
+
+        // Checked upfront, as Cloudflare would otherwise reject a chunk halfway through
+        let entry_filter = if self.only_additions {
+            Some(PatchEntryFilter::Additions)
+        } else if self.only_deletions {
+            Some(PatchEntryFilter::Deletions)
+        } else {
+            None
+        };
+        let diff = diff_env_vars(&client, &self.project, &new_vars, self.mode)?
+            .ignoring_keys(&self.ignore_keys);
+        let diff = match entry_filter {
+            Some(filter) => diff.only(filter),
+            None => diff,
+        };
+        for violation in find_var_limit_violations(&diff, MAX_VARS_PER_ENVIRONMENT) {
+            if self.strict {
+                anyhow::bail!(violation);
+            }
+            eprintln!("Warning: {violation}");
+        }
+
+        if self.dry_run_diff {
+            // Rejected like the real run would, so that a clean dry run can be relied on
+            check_required_keys(&diff, &self.require_keys)?;
+            if diff.is_empty() {
+                print_notice("No changes detected.");
+            } else {
+                print_value_changes(&diff.to_value_changes());
+            }
+            return Ok(());
+        }
+
+        let progress = Progress::new("Submitted requests");
+        let summary = apply_env_vars(
+            &client,
+            &self.project,
+            &new_vars,
+            &ApplyOptions {
+                max_vars_per_request: self.max_vars_per_request.map(|max| max as usize),
+                mode: self.mode,
+                max_retries: self.max_retries,
+                retry_budget: self.retry_budget,
+                patch_retry_on: self.retry_on.unwrap_or(RetryOn::PATCH_DEFAULT),
+                get_retry_on: self.retry_on.unwrap_or(RetryOn::GET_DEFAULT),
+                recompute_on_retry: self.recompute_on_retry,
+                required_keys: self.require_keys.clone(),
+                ignored_keys: self.ignore_keys.clone(),
+                entry_filter,
+                ..Default::default()
+            },
+            |done, total| progress.update(done, total),
+        )?;
+        if self.report_json {
+            let report = SetEnvVarsReport {
+                summary: &summary,
+                applied: !summary.is_empty(),
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else if summary.is_empty() {
+            print_notice("No changes detected. Not submitting patch.");
+        } else {
+            print_change_summary(&summary, self.only_changed, Stream::Stderr);
+            print_notice("Environment variables successfully updated");
+        }
+        if trimmed_count > 0 && !self.report_json {
+            print_notice(&format!(
+                "Trimmed surrounding whitespace from {trimmed_count} value(s)"
+            ));
+        }
+        if let (Some(state_file), Some(hash)) = (&self.state_file, desired_hash) {
+            write_file_atomically(state_file, &format!("{hash}\n"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the `@file:` values of the input file at `location`, resolving relative paths
+    /// against its directory. The files are only read once all values are merged, so that their
+    /// content is used verbatim.
+    fn resolve_file_values(&self, vars: &mut EnvVarsFile, location: &str) -> Result<()> {
+        let base_dir = match Path::new(location).parent() {
+            Some(parent) if location != "-" => parent.to_owned(),
+            _ => PathBuf::new(),
+        };
+
+        for (key, value) in vars.iter_mut() {
+            let path = match file_value(value, &base_dir) {
+                Some(path) => path,
+                None => continue,
+            };
+            if !self.allow_file_values {
+                if self.strict {
+                    anyhow::bail!(
+                        "value of {key} references a file. Pass --allow-file-values to read it"
+                    );
+                }
+                eprintln!(
+                    "Warning: value of {key} looks like a file reference but is uploaded \
+                    literally. Pass --allow-file-values to read it"
+                );
+                continue;
+            }
+            if is_url(location) {
+                anyhow::bail!(
+                    "value of {key} references a file, which is not allowed in remote input {location}"
+                );
+            }
+
+            *value = format!("{FILE_VALUE_PREFIX}{}", path.display());
+        }
+
+        Ok(())
+    }
+}
+
+impl FromEnvFile {
+    fn run(self) -> Result<()> {
+        let client = self.client.build_client()?;
+
+        let content = read_input_file(&client, &self.file)?;
+        let input_format = self.input_format.unwrap_or_else(|| {
+            if self.file.ends_with(".toml") {
+                EnvFileFormat::Netlify
+            } else {
+                EnvFileFormat::Dotenv
+            }
+        });
+        let env_vars = match input_format {
+            EnvFileFormat::Dotenv => self.read_dotenv(&content)?,
+            EnvFileFormat::Netlify => {
+                if self.comments_file.is_some() {
+                    anyhow::bail!("--comments-file is only supported for .env files");
+                }
+                let config: NetlifyConfig = FileFormat::Toml
+                    .deserialize(&content)
+                    .with_context(|| format!("invalid netlify.toml file {}", self.file))?;
+                config.environment_vars(self.environment)
+            }
+        };
+
+        let env_vars = match self.transform_keys {
+            Some(transform) => transform_keys(env_vars, transform)?,
+            None => env_vars,
+        };
+        let env_vars = env_vars
+            .into_iter()
+            .map(|(key, value)| (key, Some(EnvVar::plain(value))))
+            .collect();
+        let all_vars = match self.environment {
+            Environment::Production => EnvVarsFile {
+                production: Some(env_vars),
+                preview: None,
+            },
+            Environment::Preview => EnvVarsFile {
+                production: None,
+                preview: Some(env_vars),
+            },
+        };
+
+        let format = self.format.unwrap_or_else(|| match &self.output {
+            Some(output) => FileFormat::from_path(output),
+            None => FileFormat::Json,
+        });
+        let content = format.serialize(&all_vars)?;
+
+        if let Some(output) = self.output {
+            std::fs::write(&output, format!("{content}\n"))?;
+
+            print_notice(&format!(
+                "Environment variables written to: {}",
+                output.to_string_lossy()
+            ));
+        } else {
+            println!("{content}");
+        }
+
+        Ok(())
+    }
+
+    fn read_dotenv(&self, content: &str) -> Result<BTreeMap<String, String>> {
+        let entries =
+            parse_dotenv(content).with_context(|| format!("invalid .env file {}", self.file))?;
+
+        for (key, lines) in find_duplicate_keys(&entries) {
+            let message = format!(
+                "key {} assigned multiple times on lines {}",
+                key,
+                lines
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            if self.strict {
+                anyhow::bail!(message);
+            }
+            eprintln!("Warning: {message}. Keeping the last one");
+        }
+
+        if let Some(comments_file) = &self.comments_file {
+            let comments = collect_comments(&entries);
+            let comments = match self.transform_keys {
+                Some(transform) => transform_keys(comments, transform)?,
+                None => comments,
+            };
+            let content = FileFormat::from_path(comments_file).serialize(&comments)?;
+            std::fs::write(comments_file, format!("{content}\n"))?;
+        }
+
+        // Later assignments win, like in most dotenv implementations
+        Ok(entries
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect())
+    }
+}
+
+impl Prune {
+    fn run(self) -> Result<()> {
+        // The project is looked at several times before and while applying the changes
+        let client = CachedApi::new(self.credentials.into_client(self.client.build_client()?)?);
+
+        let mut pruned_vars = fetch_env_vars(&client, &self.project, None)?;
+        for (environment, env) in [
+            (Environment::Production, &mut pruned_vars.production),
+            (Environment::Preview, &mut pruned_vars.preview),
+        ] {
+            if self.only.is_none_or(|only| only == environment) {
+                if let Some(env) = env {
+                    env.retain(|key, _| !glob_match(&self.pattern, key));
+                }
+            }
+        }
+
+        let diff = diff_env_vars(&client, &self.project, &pruned_vars, PatchMode::Replace)?;
+        if diff.is_empty() {
+            print_notice("No variables match the pattern.");
+            return Ok(());
+        }
+
+        print_change_summary(&diff.summary(), false, Stream::Stderr);
+
+        if self.dry_run.dry_run {
+            print_notice("Dry run. No variables deleted.");
+            return Ok(());
+        }
+
+        if !self.yes {
+            if !std::io::stdin().is_terminal() {
+                anyhow::bail!(
+                    "refusing to delete variables without confirmation. Use --yes to skip"
+                );
+            }
+
+            eprint!("Delete these variables? [y/N] ");
+            std::io::stderr().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                print_notice("Aborted.");
+                return Ok(());
+            }
+        }
+
+        client.patch_project(
+            &self.project,
+            &CloudflarePagesPatchRequest {
+                deployment_configs: diff.patch,
+            },
+        )?;
+        print_notice("Variables successfully deleted");
+
+        Ok(())
+    }
+}
+
+impl Validate {
+    fn run(self) -> Result<()> {
+        let format = self
+            .format
+            .unwrap_or_else(|| FileFormat::from_path(&self.file));
+        let vars: EnvVarsFile = format
+            .deserialize(&std::fs::read_to_string(&self.file)?)
+            .with_context(|| format!("invalid syntax in {}", self.file.display()))?;
+
+        let problems = validate_env_vars(&vars, &self.require_keys);
+        if !problems.is_empty() {
+            for problem in problems.iter() {
+                eprintln!("{problem}");
+            }
+            anyhow::bail!(
+                "found {} problem(s) in {}",
+                problems.len(),
+                self.file.display()
+            );
+        }
+
+        print_notice(&format!("{} is valid", self.file.display()));
+
+        Ok(())
+    }
+}
+
+impl ListProjects {
+    fn run(self) -> Result<()> {
+        if !self.fields.is_empty() && self.format != ProjectsFormat::Json {
+            anyhow::bail!("--fields is only supported with --format json");
+        }
+
+        let client = self.credentials.into_client(self.client.build_client()?)?;
+
+        let mut projects = vec![];
+        let mut page = 1;
+        loop {
+            let page_projects = client.list_projects(page)?;
+            if page_projects.items.is_empty() {
+                break;
+            }
+            projects.extend(page_projects.items);
+            if page_projects
+                .total_count
+                .is_some_and(|total_count| projects.len() >= total_count)
+            {
+                break;
+            }
+            page += 1;
+        }
+
+        match self.format {
+            ProjectsFormat::Text => {
+                for project in projects.iter() {
+                    println!("{}  {}", project.id, project.name);
+                }
+                if projects.is_empty() {
+                    print_notice("No projects found.");
+                }
+            }
+            ProjectsFormat::Json if self.fields.is_empty() => {
+                println!("{}", serde_json::to_string_pretty(&projects)?);
+            }
+            ProjectsFormat::Json => {
+                let projects = projects
+                    .iter()
+                    .map(|project| select_fields(&serde_json::to_value(project)?, &self.fields))
+                    .collect::<Result<Vec<_>>>()?;
+                println!("{}", serde_json::to_string_pretty(&projects)?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ListDeployments {
+    fn run(self) -> Result<()> {
+        let client = self.credentials.into_client(self.client.build_client()?)?;
+
+        let created_after = match self.since {
+            Some(since) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+                Some(now.saturating_sub(since).as_secs() as i64)
+            }
+            None => None,
+        };
+        let filter = DeploymentFilter {
+            created_after,
+            environment: self.environment,
+            limit: self.limit,
+            per_page: self.per_page.map(|per_page| per_page as usize),
+            start_page: self.start_page.map(|start_page| start_page as usize),
+        };
+
+        // Deployments are printed as soon as each page arrives
+        let mut count = 0;
+        let result = for_each_deployment(&client, &self.project, &filter, |deployment| {
+            count += 1;
+            match self.format {
+                DeploymentsFormat::Text => println!(
+                    "{}  {:<10}  {}",
+                    deployment.id, deployment.environment, deployment.created_on
+                ),
+                DeploymentsFormat::Ndjson => println!(
+                    "{}",
+                    serde_json::to_string(&DeploymentLine {
+                        id: &deployment.id,
+                        environment: deployment.environment,
+                        created_on: &deployment.created_on,
+                    })?
+                ),
+            }
+            Ok(())
+        });
+
+        match (result, self.format) {
+            (Err(err), DeploymentsFormat::Ndjson) => {
+                // Lets consumers of the stream tell an incomplete listing apart
+                println!(
+                    "{}",
+                    serde_json::to_string(&serde_json::json!({ "error": format!("{err:#}") }))?
+                );
+                Err(err)
+            }
+            (Err(err), DeploymentsFormat::Text) => Err(err),
+            (Ok(_), DeploymentsFormat::Text) if count == 0 => {
+                print_notice("No deployments found.");
+                Ok(())
+            }
+            (Ok(total_count), _) => {
+                if let Some(total_count) = total_count {
+                    print_notice(&format!("Showing {count} of {total_count} deployments"));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Info {
+    fn run(self) -> Result<()> {
+        let client = self.credentials.into_client(self.client.build_client()?)?;
+
+        let info = ProjectInfo::from(&client.get_project(&self.project)?);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        } else {
+            println!("Project ID: {}", info.id);
+            println!("Project name: {}", info.name);
+            for (environment, env_info) in [
+                (Environment::Production, &info.production),
+                (Environment::Preview, &info.preview),
+            ] {
+                println!(
+                    "{} variables: {} ({} secret)",
+                    environment,
+                    env_info.var_count,
+                    env_info.secrets.len()
+                );
+                for secret in env_info.secrets.iter() {
+                    println!("  {secret} (secret)");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CompareEnvironments {
+    fn run(self) -> Result<()> {
+        let client = self.credentials.into_client(self.client.build_client()?)?;
+
+        let vars: FullEnvVarsFile = client.get_project(&self.project)?.deployment_configs.into();
+        let comparison = EnvironmentComparison::new(&vars);
+
+        if comparison.is_empty() {
+            print_notice("No differences between production and preview.");
+        }
+        for (title, keys) in [
+            ("Only in production:", &comparison.only_in_production),
+            ("Only in preview:", &comparison.only_in_preview),
+        ] {
+            if !keys.is_empty() {
+                println!("{title}");
+                for key in keys.iter() {
+                    println!("  {key}");
+                }
+            }
+        }
+        if !comparison.different.is_empty() {
+            println!("Different values:");
+            for key in comparison.different.iter() {
+                if self.keys_only {
+                    println!("  {key}");
+                } else {
+                    println!(
+                        "  {key}: {} (production) -> {} (preview)",
+                        render_compared_value(&vars.production[key]),
+                        render_compared_value(&vars.preview[key])
+                    );
+                }
+            }
+        }
+        if !comparison.secrets_not_compared.is_empty() {
+            println!("Secrets in both environments, not compared:");
+            for key in comparison.secrets_not_compared.iter() {
+                println!("  {key}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn render_compared_value(var: &EnvVar) -> String {
+    if var.secret {
+        "<secret>".to_owned()
+    } else {
+        serde_json::Value::String(var.value.to_owned()).to_string()
+    }
+}
+
+impl Diff {
+    fn run(self) -> Result<()> {
+        let http_client = self.client.build_client()?;
+
+        let format = self
+            .format
+            .unwrap_or_else(|| FileFormat::from_path(&self.file));
+        let new_vars: EnvVarsFile = format
+            .deserialize(&read_input_file(&http_client, &self.file)?)
+            .with_context(|| format!("invalid syntax in {}", self.file))?;
+
+        let diff = match (&self.base, &self.project) {
+            (Some(base), _) => {
+                let format = self.format.unwrap_or_else(|| FileFormat::from_path(base));
+                let base_vars: EnvVarsFile = format
+                    .deserialize(&read_input_file(&http_client, base)?)
+                    .with_context(|| format!("invalid syntax in {base}"))?;
+                EnvVarsDiff::new(base_vars.into_full(), &new_vars, self.mode)
+            }
+            (None, Some(project)) => {
+                let client = self.credentials.into_client(http_client)?;
+                diff_env_vars(&client, project, &new_vars, self.mode)?
+            }
+            (None, None) => unreachable!("--project is required without --base"),
+        };
 
-#[derive(Debug, Clone, Parser)]
-struct CredentialsArgs {
-    #[clap(long, env = "CLOUDFLARE_ACCOUNT", help = "Cloudflare account ID")]
-    account: String,
-    #[clap(long, env = "CLOUDFLARE_TOKEN", help = "Cloudflare access token")]
-    token: String,
-}
+        if self.diff_only_keys {
+            let summary = diff.summary().keys_only();
+            if summary.is_empty() {
+                print_notice("No changes detected.");
+                return Ok(());
+            }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CloudflareResponse<T> {
-    result: T,
-    success: bool,
-}
+            print_change_summary(&summary, self.only_changed, Stream::Stdout);
+            if self.exit_code {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CloudflarePagesProject {
-    id: String,
-    name: String,
-    deployment_configs: CloudflarePagesDeploymentConfigs,
-}
+        if diff.is_empty() {
+            print_notice("No changes detected.");
+            return Ok(());
+        }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CloudflarePagesDeployment {
-    id: String,
-    environment: Environment,
-    #[serde(flatten)]
-    vars: CloudflarePagesEnvironment,
-}
+        match self.diff_format {
+            DiffFormat::Summary => {
+                print_change_summary(&diff.summary(), self.only_changed, Stream::Stdout)
+            }
+            DiffFormat::Unified => {
+                for line in diff.to_unified().lines() {
+                    if line.starts_with("---") || line.starts_with("+++") {
+                        println!("{}", line.if_supports_color(Stdout, |text| text.bold()));
+                    } else if line.starts_with('-') {
+                        println!("{}", line.if_supports_color(Stdout, |text| text.red()));
+                    } else {
+                        println!("{}", line.if_supports_color(Stdout, |text| text.green()));
+                    }
+                }
+            }
+            DiffFormat::Values => print_value_changes(&diff.to_value_changes()),
+        }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CloudflarePagesPatchRequest {
-    deployment_configs: CloudflarePagesDeploymentConfigs,
-}
+        if self.exit_code {
+            std::process::exit(1);
+        }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CloudflarePagesDeploymentConfigs {
-    preview: CloudflarePagesEnvironment,
-    production: CloudflarePagesEnvironment,
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CloudflarePagesEnvironment {
-    env_vars: Option<BTreeMap<String, Option<CloudflarePagesEnvVarValue>>>,
-}
+impl Doctor {
+    fn run(self) -> Result<()> {
+        let mut problems = 0;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CloudflarePagesEnvVarValue {
-    r#type: CloudflarePagesEnvVarValueType,
-    value: String,
-}
+        println!("Version:    cf-pages-cli {}", env!("CARGO_PKG_VERSION"));
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-enum CloudflarePagesEnvVarValueType {
-    PlainText,
-}
+        let credentials = self.credentials;
+        let token = match (&credentials.token_env, &credentials.token) {
+            (Some(name), _) => match read_named_env_var("--token-env", name) {
+                Ok(token) => {
+                    println!("Token:      present (redacted), from environment variable {name}");
+                    Some(token)
+                }
+                Err(err) => {
+                    println!("Token:      {err:#}");
+                    None
+                }
+            },
+            (None, Some(token)) => {
+                println!(
+                    "Token:      present (redacted), from {}",
+                    credential_source("CLOUDFLARE_TOKEN", token)
+                );
+                Some(token.to_owned())
+            }
+            (None, None) => {
+                println!("Token:      missing. Pass --token or set CLOUDFLARE_TOKEN");
+                None
+            }
+        };
+        if token.is_none() {
+            problems += 1;
+        }
+        match (credentials.auth_mode, &credentials.email) {
+            (AuthMode::Bearer, _) => println!("Auth mode:  bearer"),
+            (AuthMode::Legacy, Some(email)) => println!("Auth mode:  legacy, as {email}"),
+            (AuthMode::Legacy, None) => {
+                println!("Auth mode:  legacy, but no email given. Pass --email");
+                problems += 1;
+            }
+        }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct FullEnvVarsFile {
-    production: BTreeMap<String, String>,
-    preview: BTreeMap<String, String>,
-}
+        let account = match (&credentials.account_env, &credentials.account) {
+            (Some(name), _) => match read_named_env_var("--account-env", name) {
+                Ok(account) => {
+                    println!("Account:    {account}, from environment variable {name}");
+                    Some(account)
+                }
+                Err(err) => {
+                    println!("Account:    {err:#}");
+                    problems += 1;
+                    None
+                }
+            },
+            (None, Some(account)) => {
+                println!(
+                    "Account:    {account}, from {}",
+                    credential_source("CLOUDFLARE_ACCOUNT", account)
+                );
+                Some(account.to_owned())
+            }
+            (None, None) => {
+                println!("Account:    not given, discovered from the token");
+                None
+            }
+        };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct EnvVarsFile {
-    production: Option<BTreeMap<String, String>>,
-    preview: Option<BTreeMap<String, String>>,
-}
+        println!(
+            "Timeout:    {}s per request, {}s to connect",
+            self.client.timeout, self.client.connect_timeout
+        );
+        let proxies = [
+            "HTTPS_PROXY",
+            "https_proxy",
+            "HTTP_PROXY",
+            "http_proxy",
+            "ALL_PROXY",
+            "all_proxy",
+            "NO_PROXY",
+            "no_proxy",
+        ]
+        .into_iter()
+        .filter_map(|name| {
+            std::env::var(name)
+                .ok()
+                .map(|value| format!("{name}={}", redact_url_userinfo(&value)))
+        })
+        .collect::<Vec<_>>();
+        if proxies.is_empty() {
+            println!("Proxy:      none");
+        } else {
+            println!("Proxy:      {}", proxies.join(", "));
+        }
+        println!("API:        {}", credentials.api_base_url);
 
-impl FromStr for Environment {
-    type Err = &'static str;
+        if let Some(token) = token {
+            let client = credentials.build_api_client(
+                self.client.build_client()?,
+                account.clone(),
+                token,
+            )?;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "production" => Ok(Self::Production),
-            "preview" => Ok(Self::Preview),
-            _ => Err("unknown value"),
+            match client.verify_token() {
+                Ok(verification) => {
+                    println!("Connection: ok, token is {}", verification.status);
+                    if verification.status != "active" {
+                        problems += 1;
+                    }
+                }
+                Err(err) => {
+                    println!("Connection: failed: {err:#}");
+                    problems += 1;
+                }
+            }
+
+            if account.is_none() {
+                match client.with_sole_account() {
+                    Ok(client) => println!("Resolved:   account {}", client.account()),
+                    Err(err) => {
+                        println!("Resolved:   {err:#}");
+                        problems += 1;
+                    }
+                }
+            }
+        }
+
+        if problems > 0 {
+            anyhow::bail!("{problems} check(s) failed");
         }
+        Ok(())
     }
 }
 
-impl ValueEnum for Environment {
-    fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Production, Self::Preview]
+impl Subcommands {
+    /// The credentials of commands that talk to Cloudflare.
+    fn credentials_mut(&mut self) -> Option<&mut CredentialsArgs> {
+        match self {
+            Self::GetEnvVars(cmd) => Some(&mut cmd.credentials),
+            Self::GetVar(cmd) => Some(&mut cmd.credentials),
+            Self::SetEnvVars(cmd) => Some(&mut cmd.credentials),
+            Self::Prune(cmd) => Some(&mut cmd.credentials),
+            Self::ListProjects(cmd) => Some(&mut cmd.credentials),
+            Self::ListDeployments(cmd) => Some(&mut cmd.credentials),
+            Self::Info(cmd) => Some(&mut cmd.credentials),
+            Self::Diff(cmd) => Some(&mut cmd.credentials),
+            Self::CompareEnvironments(cmd) => Some(&mut cmd.credentials),
+            Self::Doctor(cmd) => Some(&mut cmd.credentials),
+            Self::ToEnvFile(_) | Self::FromEnvFile(_) | Self::Validate(_) | Self::Config(_) => None,
+        }
     }
+}
 
-    fn to_possible_value(&self) -> Option<PossibleValue> {
-        match self {
-            Environment::Production => Some(PossibleValue::new("production")),
-            Environment::Preview => Some(PossibleValue::new("preview")),
+impl ConfigCommand {
+    fn run(self) -> Result<()> {
+        let path = config_path()
+            .context("unable to determine the config file location. Use --config instead")?;
+
+        match self.command {
+            ConfigSubcommands::Set(cmd) => {
+                let content = if path.exists() {
+                    std::fs::read_to_string(&path)?
+                } else {
+                    String::new()
+                };
+                let content = set_config_value(&content, cmd.key, &cmd.value)
+                    .with_context(|| format!("invalid config file {}", path.display()))?;
+                write_file_atomically(&path, &content)?;
+
+                print_notice(&format!(
+                    "Updated {} in {}",
+                    cmd.key.as_str(),
+                    path.display()
+                ));
+            }
+            ConfigSubcommands::Show => {
+                if !path.exists() {
+                    anyhow::bail!("config file {} does not exist", path.display());
+                }
+                let content = show_config(&std::fs::read_to_string(&path)?)
+                    .with_context(|| format!("invalid config file {}", path.display()))?;
+
+                print!("{content}");
+            }
+            ConfigSubcommands::Path => println!("{}", path.display()),
         }
+
+        Ok(())
     }
 }
 
-impl Serialize for Environment {
-    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(match self {
-            Environment::Production => "production",
-            Environment::Preview => "preview",
-        })
+impl OutputFormat {
+    /// Infers the format from the extension of `path`, including `.txt` and `.env` files.
+    fn from_path(path: &Path) -> Self {
+        if path.file_name().is_some_and(|name| name == ".env") {
+            return Self::Dotenv;
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("env") => Self::Dotenv,
+            Some("txt") => Self::Text,
+            _ => FileFormat::from_path(path).into(),
+        }
     }
 }
 
-impl<'de> Deserialize<'de> for Environment {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let value = String::deserialize(deserializer)?;
-        match value.parse() {
-            Ok(value) => Ok(value),
-            Err(err) => Err(serde::de::Error::custom(format!(
-                "invalid environment string: {err}"
-            ))),
+impl From<FileFormat> for OutputFormat {
+    fn from(value: FileFormat) -> Self {
+        match value {
+            FileFormat::Json => Self::Json,
+            FileFormat::Toml => Self::Toml,
         }
     }
 }
 
-impl GetEnvVars {
-    fn run(self) -> Result<()> {
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(10))
-            .build()?;
-
-        let existing_vars: EnvVarsFile = if let Some(deployment) = self.deployment {
-            let deployment_response: CloudflareResponse<CloudflarePagesDeployment> = client
-                .get(format!(
-                    "https://api.cloudflare.com/client/v4/accounts/{}/pages/projects/{}/deployments/{}",
-                    self.credentials.account, self.project, deployment
-                ))
-                .header(
-                    "Authorization",
-                    format!("Bearer {}", self.credentials.token),
-                )
-                .send()?
-                .json()?;
-            if !deployment_response.success {
-                anyhow::bail!("unsuccessful Cloudflare request");
+impl CredentialsArgs {
+    /// Builds the API client without discovering the account or checking anything.
+    fn build_api_client(
+        &self,
+        client: Client,
+        account: Option<String>,
+        token: String,
+    ) -> Result<CloudflareClient> {
+        let api_client = CloudflareClient::new(client, account.unwrap_or_default(), token)
+            .with_api_base_url(self.api_base_url.clone());
+        Ok(match (self.auth_mode, &self.email) {
+            (AuthMode::Bearer, _) => api_client,
+            (AuthMode::Legacy, Some(email)) => api_client.with_legacy_auth(email.to_owned()),
+            (AuthMode::Legacy, None) => {
+                anyhow::bail!("--auth-mode legacy requires --email or CLOUDFLARE_EMAIL")
             }
+        })
+    }
 
-            let deployment = deployment_response.result;
-            let vars: BTreeMap<String, String> = deployment.vars.into();
+    /// Fills in the credentials found in the config file, without overriding values given on the
+    /// command line, in the environment or in --credentials-file.
+    fn load_config(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read config file {}", path.display()))?;
+        let config: Config = FileFormat::Toml
+            .deserialize(&content)
+            .with_context(|| format!("invalid config file {}", path.display()))?;
 
-            match deployment.environment {
-                Environment::Production => EnvVarsFile {
-                    production: Some(vars),
-                    preview: None,
-                },
-                Environment::Preview => EnvVarsFile {
-                    production: None,
-                    preview: Some(vars),
-                },
-            }
-        } else {
-            let project_response: CloudflareResponse<CloudflarePagesProject> = client
-                .get(format!(
-                    "https://api.cloudflare.com/client/v4/accounts/{}/pages/projects/{}",
-                    self.credentials.account, self.project
-                ))
-                .header(
-                    "Authorization",
-                    format!("Bearer {}", self.credentials.token),
-                )
-                .send()?
-                .json()?;
-            if !project_response.success {
-                anyhow::bail!("unsuccessful Cloudflare request");
+        for (name, field, value) in [
+            ("CLOUDFLARE_ACCOUNT", &mut self.account, config.account),
+            ("CLOUDFLARE_TOKEN", &mut self.token, config.token),
+        ] {
+            if field.is_none() && value.is_some() {
+                *field = value;
+                record_credential_source(name, format!("config file {}", path.display()));
             }
+        }
 
-            project_response.result.deployment_configs.into()
-        };
+        Ok(())
+    }
 
-        if let Some(output) = self.output {
-            let mut dump_file = std::fs::File::create(&output)?;
-            serde_json::to_writer_pretty(&mut dump_file, &existing_vars)?;
+    /// The account passed via --account or --account-env, if any.
+    fn configured_account(&self) -> Result<Option<String>> {
+        Ok(match &self.account_env {
+            Some(name) => Some(read_named_env_var("--account-env", name)?),
+            None => self.account.clone(),
+        })
+    }
 
-            // EOF line for Unix platforms
-            writeln!(&mut dump_file)?;
+    /// Builds the API client, discovering the account from the token if not provided. This
+    /// happens once, so the account stays the same for the rest of the run.
+    fn into_client(self, client: Client) -> Result<CloudflareClient> {
+        let account = self.configured_account()?;
+        let token = match (&self.token_env, &self.token) {
+            (Some(name), _) => read_named_env_var("--token-env", name)?,
+            (None, Some(token)) => token.to_owned(),
+            (None, None) => {
+                anyhow::bail!("no access token provided. Pass --token or set CLOUDFLARE_TOKEN")
+            }
+        };
 
-            println!(
-                "Environment variables written to: {}",
-                output.to_string_lossy()
-            );
-        } else {
-            let json = serde_json::to_string_pretty(&existing_vars)?;
-            println!("{json}");
+        let api_client = self.build_api_client(client, account.clone(), token)?;
+
+        if self.check_scopes {
+            match api_client.token_permissions() {
+                Ok(permissions) => {
+                    for permission in find_missing_pages_permissions(&permissions) {
+                        eprintln!("Warning: the token lacks the {permission} permission");
+                    }
+                }
+                Err(err) => eprintln!("Warning: unable to check the token permissions: {err:#}"),
+            }
         }
 
-        Ok(())
+        match account {
+            Some(_) => Ok(api_client),
+            None => api_client
+                .with_sole_account()
+                .context("unable to discover the account from the token"),
+        }
     }
 }
 
-impl SetEnvVars {
-    fn run(self) -> Result<()> {
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(10))
-            .build()?;
-
-        let project_response: CloudflareResponse<CloudflarePagesProject> = client
-            .get(format!(
-                "https://api.cloudflare.com/client/v4/accounts/{}/pages/projects/{}",
-                self.credentials.account, self.project
-            ))
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.credentials.token),
-            )
-            .send()?
-            .json()?;
-        if !project_response.success {
-            anyhow::bail!("unsuccessful Cloudflare request");
+/// Opens the current variables of a project in the user's editor as a temporary file. Returns the
+/// edited variables, or `None` if the file was saved unchanged. Fails without returning anything if
+/// the editor exits with an error.
+fn edit_env_vars(
+    client: &CloudflareClient,
+    project: &str,
+    format: FileFormat,
+) -> Result<Option<EnvVarsFile>> {
+    let mut env_vars = fetch_env_vars(client, project, None)?;
+    // Left untouched, empty secret values keep the secrets as they are
+    env_vars.strip_secret_values();
+    let content = format!("{}\n", format.serialize(&env_vars)?);
+
+    let extension = match format {
+        FileFormat::Json => "json",
+        FileFormat::Toml => "toml",
+    };
+    // Created exclusively under an unpredictable name, so that nothing planted in the shared temp
+    // directory, such as a symlink, is ever written through
+    let mut attempts = 0;
+    let (path, mut file) = loop {
+        let path = std::env::temp_dir().join(format!(
+            "cf-pages-{}-{}.{}",
+            project,
+            Uuid::new_v4().simple(),
+            extension
+        ));
+        match create_output_file(&path, Some(SECRET_FILE_MODE), false) {
+            Ok(file) => break (path, file),
+            Err(_) if attempts < 10 && path.symlink_metadata().is_ok() => attempts += 1,
+            Err(err) => return Err(err),
         }
+    };
+    file.write_all(content.as_bytes())?;
+    drop(file);
 
-        let existing_vars: FullEnvVarsFile = project_response.result.deployment_configs.into();
+    let edited = run_editor(&path).and_then(|_| Ok(std::fs::read_to_string(&path)?));
+    let _ = std::fs::remove_file(&path);
+    let edited = edited?;
 
-        let new_vars: EnvVarsFile = serde_json::from_reader(&mut std::fs::File::open(&self.file)?)?;
+    if edited == content {
+        return Ok(None);
+    }
+    Ok(Some(
+        format
+            .deserialize(&edited)
+            .context("invalid syntax in the edited file")?,
+    ))
+}
 
-        let deployment_configs_patch = generate_deployment_configs_patch(&existing_vars, &new_vars);
-        if deployment_configs_patch.is_empty() {
-            println!("No changes detected. Not submitting patch.");
-        } else {
-            let patch_response: CloudflareResponse<CloudflarePagesProject> = client
-                .patch(format!(
-                    "https://api.cloudflare.com/client/v4/accounts/{}/pages/projects/{}",
-                    self.credentials.account, self.project
-                ))
-                .header(
-                    "Authorization",
-                    format!("Bearer {}", self.credentials.token),
-                )
-                .json(&CloudflarePagesPatchRequest {
-                    deployment_configs: deployment_configs_patch,
-                })
-                .send()?
-                .json()?;
-            if !patch_response.success {
-                anyhow::bail!("unsuccessful Cloudflare request");
+/// Runs `$VISUAL` or `$EDITOR` on a file and waits for it to exit, falling back to `vi`, or
+/// `notepad` on Windows. The variable may contain arguments, e.g. `code --wait`.
+fn run_editor(path: &Path) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_owned()
+            } else {
+                "vi".to_owned()
             }
+        });
+
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or_default();
+    let status = std::process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .with_context(|| format!("unable to run editor {program}"))?;
+    if !status.success() {
+        anyhow::bail!("editor exited with {status}. Not submitting patch");
+    }
+    Ok(())
+}
+
+/// Reads the variable named by `--token-env` or `--account-env`, failing if it is not set.
+fn read_named_env_var(option: &str, name: &str) -> Result<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .with_context(|| format!("environment variable {name} given by {option} is not set"))
+}
+
+impl ClientArgs {
+    fn build_client(&self) -> Result<Client> {
+        let request_id = match &self.request_id {
+            Some(request_id) => request_id.to_owned(),
+            None => Uuid::new_v4().to_string(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-Request-Id",
+            HeaderValue::from_str(&request_id).context("invalid request ID")?,
+        );
+
+        let mut builder = ClientBuilder::new()
+            .user_agent(concat!("cf-pages-cli/", env!("CARGO_PKG_VERSION")))
+            .default_headers(headers)
+            .timeout(Duration::from_secs(self.timeout))
+            .connect_timeout(Duration::from_secs(self.connect_timeout))
+            // Responses listing many variables compress well
+            .gzip(true)
+            .deflate(true)
+            .redirect(redirect_policy(self.follow_redirects));
 
-            println!("Environment variables successfully updated");
+        if self.insecure {
+            eprintln!(
+                "Warning: TLS certificate verification is disabled. Only use --insecure for testing."
+            );
+            builder = builder.danger_accept_invalid_certs(true);
         }
 
-        Ok(())
+        Ok(builder.build()?)
     }
 }
 
 impl ToEnvFile {
     fn run(self) -> Result<()> {
-        let all_vars: EnvVarsFile = serde_json::from_reader(&mut std::fs::File::open(self.file)?)?;
-        let target_env_vars = match self.environment {
-            Environment::Production => all_vars.production,
-            Environment::Preview => all_vars.preview,
-        };
+        let client = self.client.build_client()?;
 
-        let target_env_vars = match target_env_vars {
-            Some(value) => value,
-            None => anyhow::bail!("empty environment"),
+        let all_vars: EnvVarsFile = FileFormat::from_path(&self.file)
+            .deserialize(&read_input_file(&client, &self.file)?)?;
+        let target_env_vars = match (self.environment, self.merge_order) {
+            (EnvironmentSelection::Production, _) => all_vars.production,
+            (EnvironmentSelection::Preview, _) => all_vars.preview,
+            (EnvironmentSelection::Both, MergeOrder::ProductionFirst) => {
+                all_vars.combine(Environment::Preview)
+            }
+            (EnvironmentSelection::Both, MergeOrder::PreviewFirst) => {
+                all_vars.combine(Environment::Production)
+            }
         };
 
-        let mut buffer = String::new();
+        // Variables marked for deletion do not belong in a .env file
+        let mut target_env_vars: BTreeMap<String, EnvVar> = match target_env_vars {
+            Some(value) => value
+                .into_iter()
+                .filter_map(|(key, var)| var.map(|var| (key, var)))
+                .collect(),
+            None => {
+                let environment = match self.environment {
+                    EnvironmentSelection::Production => "production",
+                    EnvironmentSelection::Preview => "preview",
+                    EnvironmentSelection::Both => "production or preview",
+                };
+                anyhow::bail!(
+                    "no {environment} environment in {}. Files downloaded with get-env-vars \
+                    --deployment only contain the environment of the deployment, unless \
+                    --fill-missing is set",
+                    self.file
+                )
+            }
+        };
+        if self.fail_on_empty && target_env_vars.is_empty() {
+            match self.environment {
+                EnvironmentSelection::Production => {
+                    anyhow::bail!("no variables in the production environment")
+                }
+                EnvironmentSelection::Preview => {
+                    anyhow::bail!("no variables in the preview environment")
+                }
+                EnvironmentSelection::Both => anyhow::bail!("no variables in either environment"),
+            }
+        }
 
-        for (key, value) in target_env_vars.iter() {
-            if self.empty {
-                buffer.push_str(&format!("{}=\n", key));
-            } else {
-                buffer.push_str(&format!(
-                    "{}={}\n",
-                    key,
-                    value.replace("\r\n", "").replace('\n', "")
+        if self.trim {
+            let trimmed_count = trim_values(target_env_vars.values_mut().map(|var| &mut var.value));
+            if trimmed_count > 0 {
+                print_notice(&format!(
+                    "Trimmed surrounding whitespace from {trimmed_count} value(s)"
                 ));
             }
         }
 
+        if self.decode_base64 {
+            for (key, var) in target_env_vars.iter_mut() {
+                let value = &mut var.value;
+                let decoded = BASE64
+                    .decode(value.as_bytes())
+                    .with_context(|| format!("invalid base64 value for variable {key}"))?;
+                *value = String::from_utf8(decoded)
+                    .with_context(|| format!("decoded value for variable {key} is not UTF-8"))?;
+            }
+        }
+
+        let comments: BTreeMap<String, String> = match &self.comments_file {
+            Some(comments_file) => FileFormat::from_path(comments_file)
+                .deserialize(&std::fs::read_to_string(comments_file)?)
+                .with_context(|| format!("invalid syntax in {}", comments_file.display()))?,
+            None => BTreeMap::new(),
+        };
+        let (target_env_vars, comments) = match self.transform_keys {
+            Some(transform) => (
+                transform_keys(target_env_vars, transform)?,
+                transform_keys(comments, transform)?,
+            ),
+            None => (target_env_vars, comments),
+        };
+        for key in comments.keys() {
+            if !target_env_vars.contains_key(key) {
+                eprintln!("Warning: dropping comment of variable {key} as it no longer exists");
+            }
+        }
+
+        let k8s_kind = match self.format {
+            ToEnvFileFormat::K8sSecret => Some(K8sManifestKind::Secret),
+            ToEnvFileFormat::K8sConfigmap => Some(K8sManifestKind::ConfigMap),
+            ToEnvFileFormat::Dotenv
+            | ToEnvFileFormat::Shell
+            | ToEnvFileFormat::Systemd
+            | ToEnvFileFormat::EnvsubstTemplate
+            | ToEnvFileFormat::Properties => None,
+        };
+
+        let buffer = if let Some(kind) = k8s_kind {
+            let values = target_env_vars
+                .iter()
+                .map(|(key, var)| {
+                    let value = if self.empty { "" } else { var.value.as_str() };
+                    (key.to_owned(), value.to_owned())
+                })
+                .collect();
+            format_k8s_manifest(
+                kind,
+                self.name.as_deref().unwrap_or_default(),
+                self.namespace.as_deref(),
+                &values,
+                self.line_ending,
+            )
+        } else if let ToEnvFileFormat::EnvsubstTemplate = self.format {
+            format!(
+                "{}{}",
+                format_envsubst_vars(target_env_vars.keys()),
+                self.line_ending.as_str()
+            )
+        } else {
+            let mut buffer = String::new();
+            for (key, value) in target_env_vars.iter() {
+                if let Some(comment) = comments.get(key) {
+                    buffer.push_str(&format_comment(comment, self.line_ending));
+                }
+                let value = if self.empty { "" } else { value.value.as_str() };
+                let line = match self.format {
+                    ToEnvFileFormat::Dotenv if self.empty => format!("{key}="),
+                    ToEnvFileFormat::Dotenv => self.env_file_quote.format_line(key, value),
+                    ToEnvFileFormat::Systemd => format_systemd_line(key, value),
+                    ToEnvFileFormat::Properties => format_properties_line(key, value),
+                    ToEnvFileFormat::Shell => format_shell_line(key, value),
+                    ToEnvFileFormat::K8sSecret
+                    | ToEnvFileFormat::K8sConfigmap
+                    | ToEnvFileFormat::EnvsubstTemplate => {
+                        unreachable!("whole-file formats are rendered above")
+                    }
+                };
+                buffer.push_str(&line);
+                buffer.push_str(self.line_ending.as_str());
+            }
+            buffer
+        };
+        let buffer = if self.no_trailing_newline {
+            buffer
+                .strip_suffix(self.line_ending.as_str())
+                .unwrap_or(&buffer)
+                .to_owned()
+        } else {
+            buffer
+        };
+
         if let Some(output) = self.output {
-            let mut dump_file = std::fs::File::create(&output)?;
+            let has_secrets = !self.empty
+                && !matches!(self.format, ToEnvFileFormat::EnvsubstTemplate)
+                && target_env_vars.values().any(|var| var.secret);
+            let mode = self
+                .output_permissions
+                .or_else(|| has_secrets.then_some(SECRET_FILE_MODE));
+            ensure_parent_dir(&output, self.create_missing_dirs)?;
+            let mut dump_file =
+                create_output_file(&output, mode, !self.fail_if_exists || self.force)?;
             dump_file.write_all(buffer.as_bytes())?;
 
-            println!(
+            print_notice(&format!(
                 "Environment variables written to: {}",
                 output.to_string_lossy()
-            );
+            ));
         } else {
             print!("{buffer}");
         }
@@ -375,118 +3001,348 @@ impl ToEnvFile {
     }
 }
 
-impl CloudflarePagesDeploymentConfigs {
-    pub fn is_empty(&self) -> bool {
-        let is_preview_empty = match &self.preview.env_vars {
-            Some(preview) => preview.is_empty(),
-            None => true,
-        };
-        let is_production_empty = match &self.production.env_vars {
-            Some(production) => production.is_empty(),
-            None => true,
-        };
+fn main() -> Result<()> {
+    if let Some(path) = find_path_option("--credentials-file", "CF_PAGES_CREDENTIALS_FILE") {
+        load_credentials_file(&path)?;
+    }
+    if let Some(path) = find_optional_path_option(
+        "--project-from-wrangler",
+        "CF_PAGES_PROJECT_FROM_WRANGLER",
+        DEFAULT_WRANGLER_PATH,
+    ) {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        let project = project_name_from_wrangler(&content)
+            .with_context(|| format!("unable to find the project name in {}", path.display()))?;
+        // `--project` given on the command line still takes precedence
+        std::env::set_var("CF_PAGES_PROJECT", project);
+    }
+
+    let mut cli = Cli::parse();
 
-        is_preview_empty && is_production_empty
+    // Only loaded by commands that use it, so that a broken config file does not get in the way of
+    // `--help`, offline commands or repairing it with `config set`
+    if let Some(credentials) = cli.command.credentials_mut() {
+        if let Some(path) = config_path().filter(|path| path.exists()) {
+            credentials.load_config(&path)?;
+        }
+    }
+
+    if cli.no_color {
+        owo_colors::set_override(false);
+    }
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+
+    match cli.command {
+        Subcommands::GetEnvVars(cmd) => cmd.run()?,
+        Subcommands::GetVar(cmd) => cmd.run()?,
+        Subcommands::SetEnvVars(cmd) => cmd.run()?,
+        Subcommands::ToEnvFile(cmd) => cmd.run()?,
+        Subcommands::FromEnvFile(cmd) => cmd.run()?,
+        Subcommands::Prune(cmd) => cmd.run()?,
+        Subcommands::Validate(cmd) => cmd.run()?,
+        Subcommands::ListProjects(cmd) => cmd.run()?,
+        Subcommands::ListDeployments(cmd) => cmd.run()?,
+        Subcommands::Info(cmd) => cmd.run()?,
+        Subcommands::Diff(cmd) => cmd.run()?,
+        Subcommands::CompareEnvironments(cmd) => cmd.run()?,
+        Subcommands::Config(cmd) => cmd.run()?,
+        Subcommands::Doctor(cmd) => cmd.run()?,
     }
+
+    Ok(())
 }
 
-impl From<CloudflarePagesDeploymentConfigs> for FullEnvVarsFile {
-    fn from(value: CloudflarePagesDeploymentConfigs) -> Self {
+impl Progress {
+    fn new(label: &'static str) -> Self {
         Self {
-            production: value.production.into(),
-            preview: value.preview.into(),
+            label,
+            enabled: !QUIET.load(Ordering::Relaxed) && std::io::stderr().is_terminal(),
+        }
+    }
+
+    fn update(&self, done: usize, total: usize) {
+        // Single-step operations finish too quickly for progress to be useful
+        if self.enabled && total > 1 {
+            eprintln!("{}: {done}/{total} ({}%)", self.label, done * 100 / total);
         }
     }
 }
 
-impl From<CloudflarePagesDeploymentConfigs> for EnvVarsFile {
-    fn from(value: CloudflarePagesDeploymentConfigs) -> Self {
-        Self {
-            production: Some(value.production.into()),
-            preview: Some(value.preview.into()),
+/// Follows up to 10 redirects, refusing ones leading to a different host or port unless
+/// `follow_cross_host` is set.
+fn redirect_policy(follow_cross_host: bool) -> Policy {
+    Policy::custom(move |attempt| {
+        let origin = &attempt.previous()[0];
+        let same_host = attempt.url().host_str() == origin.host_str()
+            && attempt.url().port_or_known_default() == origin.port_or_known_default();
+
+        if attempt.previous().len() > 10 {
+            attempt.error("too many redirects")
+        } else if same_host || follow_cross_host {
+            attempt.follow()
+        } else {
+            let message = format!(
+                "refusing to follow redirect to {}. Use --follow-redirects to allow it",
+                attempt.url().host_str().unwrap_or_default()
+            );
+            attempt.error(message)
         }
+    })
+}
+
+/// Prints a message about the outcome of a command to stderr, keeping stdout for data. Silenced by
+/// `--quiet`.
+fn print_notice(message: &str) {
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("{message}");
     }
 }
 
-impl From<CloudflarePagesEnvironment> for BTreeMap<String, String> {
-    fn from(value: CloudflarePagesEnvironment) -> Self {
-        match value.env_vars {
-            Some(env_vars) => env_vars
-                .into_iter()
-                .map(|(key, value)| {
-                    (
-                        key,
-                        value.map(|var_value| var_value.value).unwrap_or_default(),
-                    )
-                })
-                .collect(),
-            None => Self::default(),
+/// Prints the added, changed and removed keys of each environment to `stream`: stdout when the
+/// summary is the requested output, as for `diff`, and stderr when it accompanies a change.
+fn print_change_summary(summary: &ChangeSummary, only_changed: bool, stream: Stream) {
+    let print = |line: &dyn std::fmt::Display| match stream {
+        Stream::Stderr => eprintln!("{line}"),
+        Stream::Stdout => println!("{line}"),
+    };
+
+    for (environment, changes) in [
+        ("production", &summary.production),
+        ("preview", &summary.preview),
+    ] {
+        if only_changed && changes.is_empty() {
+            continue;
+        }
+
+        print(&format!("{environment}:"));
+
+        if changes.is_empty() {
+            print(&"  (no changes)");
+            continue;
+        }
+
+        for key in changes.added.iter() {
+            let line = format!("  + {key}");
+            print(&line.if_supports_color(stream, |text| text.green()));
+        }
+        for key in changes.changed.iter() {
+            let line = format!("  ~ {key}");
+            print(&line.if_supports_color(stream, |text| text.yellow()));
+        }
+        for key in changes.removed.iter() {
+            let line = format!("  - {key}");
+            print(&line.if_supports_color(stream, |text| text.red()));
         }
     }
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Prints the per-key changes of a diff, colored like [`print_change_summary`].
+fn print_value_changes(changes: &str) {
+    for line in changes.lines() {
+        if line.starts_with("  +") {
+            println!("{}", line.if_supports_color(Stdout, |text| text.green()));
+        } else if line.starts_with("  ~") {
+            println!("{}", line.if_supports_color(Stdout, |text| text.yellow()));
+        } else if line.starts_with("  -") {
+            println!("{}", line.if_supports_color(Stdout, |text| text.red()));
+        } else {
+            println!("{line}");
+        }
+    }
+}
 
-    match cli.command {
-        Subcommands::GetEnvVars(cmd) => cmd.run()?,
-        Subcommands::SetEnvVars(cmd) => cmd.run()?,
-        Subcommands::ToEnvFile(cmd) => cmd.run()?,
+/// Strips leading and trailing ASCII whitespace, returning the number of values changed.
+fn trim_values<'a, I>(values: I) -> usize
+where
+    I: IntoIterator<Item = &'a mut String>,
+{
+    values
+        .into_iter()
+        .filter_map(|value| {
+            let trimmed = value.trim_matches(|c: char| c.is_ascii_whitespace());
+            if trimmed.len() == value.len() {
+                None
+            } else {
+                *value = trimmed.to_owned();
+                Some(())
+            }
+        })
+        .count()
+}
+
+/// Renders one `<environment> <key>` line per variable.
+fn format_keys_as_text(keys: &EnvKeysFile) -> String {
+    [
+        (Environment::Production, &keys.production),
+        (Environment::Preview, &keys.preview),
+    ]
+    .into_iter()
+    .flat_map(|(environment, env_keys)| {
+        env_keys
+            .iter()
+            .flatten()
+            .map(move |key| format!("{environment} {key}"))
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Renders one `<environment> <key> [plain|secret]` line per variable.
+fn format_typed_keys_as_text(env_vars: &EnvVarsFile) -> String {
+    [
+        (Environment::Production, &env_vars.production),
+        (Environment::Preview, &env_vars.preview),
+    ]
+    .into_iter()
+    .flat_map(|(environment, env)| {
+        env.iter().flatten().map(move |(key, var)| {
+            let var_type = if var.as_ref().is_some_and(|var| var.secret) {
+                "secret"
+            } else {
+                "plain"
+            };
+            format!("{environment} {key} [{var_type}]")
+        })
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Looks up a global path option ahead of argument parsing, falling back to its environment
+/// variable.
+fn find_path_option(name: &str, env: &str) -> Option<PathBuf> {
+    let prefix = format!("{name}=");
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == name {
+            return args.next().map(PathBuf::from);
+        } else if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix(&prefix)) {
+            return Some(PathBuf::from(path));
+        }
     }
 
-    Ok(())
+    std::env::var_os(env).map(PathBuf::from)
+}
+
+/// Like [`find_path_option`], for options whose value may be omitted in favor of `default`.
+fn find_optional_path_option(name: &str, env: &str, default: &str) -> Option<PathBuf> {
+    let prefix = format!("{name}=");
+    let mut args = std::env::args_os().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == name {
+            return Some(
+                args.next_if(|next| !next.to_string_lossy().starts_with('-'))
+                    .map_or_else(|| PathBuf::from(default), PathBuf::from),
+            );
+        } else if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix(&prefix)) {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    std::env::var_os(env).map(PathBuf::from)
+}
+
+/// Resolves the config file location from `--config`, falling back to the platform default.
+fn config_path() -> Option<PathBuf> {
+    find_path_option("--config", "CF_PAGES_CONFIG").or_else(default_config_path)
+}
+
+fn config_help() -> String {
+    let default_path = match default_config_path() {
+        Some(path) => path.display().to_string(),
+        None => "none".to_owned(),
+    };
+    format!(
+        "Path to a TOML config file with account and token fields. Values given on the command \
+        line, in the environment or in --credentials-file take precedence [default: \
+        {default_path}]"
+    )
 }
 
-fn generate_deployment_configs_patch(
-    old_vars: &FullEnvVarsFile,
-    new_vars: &EnvVarsFile,
-) -> CloudflarePagesDeploymentConfigs {
-    CloudflarePagesDeploymentConfigs {
-        preview: generate_env_patch(&old_vars.preview, &new_vars.preview),
-        production: generate_env_patch(&old_vars.production, &new_vars.production),
+/// Exports the credentials found in a dotenv file, without overriding variables that are already
+/// set.
+fn load_credentials_file(path: &Path) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("unable to read credentials file {}", path.display()))?;
+    let entries = parse_dotenv(&content)
+        .with_context(|| format!("invalid credentials file {}", path.display()))?;
+
+    for entry in entries {
+        if matches!(
+            entry.key.as_str(),
+            "CLOUDFLARE_ACCOUNT" | "CLOUDFLARE_TOKEN" | "CLOUDFLARE_EMAIL"
+        ) && std::env::var_os(&entry.key).is_none()
+        {
+            std::env::set_var(&entry.key, &entry.value);
+            record_credential_source(&entry.key, format!("credentials file {}", path.display()));
+        }
     }
+
+    Ok(())
 }
 
-fn generate_env_patch(
-    old_env: &BTreeMap<String, String>,
-    new_env: &Option<BTreeMap<String, String>>,
-) -> CloudflarePagesEnvironment {
-    let mut changes: BTreeMap<String, Option<CloudflarePagesEnvVarValue>> = Default::default();
+fn record_credential_source(name: &str, source: String) {
+    if let Ok(mut sources) = CREDENTIAL_SOURCES.lock() {
+        sources.insert(name.to_owned(), source);
+    }
+}
 
-    if let Some(new_env) = new_env.as_ref() {
-        // Finds new and changed variables
-        new_env
-            .iter()
-            .filter(|(key, value)| match old_env.get(*key) {
-                Some(old_value) => {
-                    // Keep the patch minimal: do not generate entry if not necessary
-                    *value != old_value
-                }
-                None => {
-                    // This is a new env var
-                    true
-                }
-            })
-            .for_each(|(key, value)| {
-                changes.insert(
-                    key.to_owned(),
-                    Some(CloudflarePagesEnvVarValue {
-                        r#type: CloudflarePagesEnvVarValueType::PlainText,
-                        value: value.to_owned(),
-                    }),
-                );
-            });
+/// Describes where a credential passed to the CLI as `value` came from, given the environment
+/// variable the flag falls back to.
+fn credential_source(env_name: &str, value: &str) -> String {
+    let recorded = CREDENTIAL_SOURCES
+        .lock()
+        .ok()
+        .and_then(|sources| sources.get(env_name).cloned());
+    match recorded {
+        Some(source) => source,
+        None if std::env::var(env_name).is_ok_and(|env_value| env_value == value) => {
+            format!("environment variable {env_name}")
+        }
+        None => "command line".to_owned(),
+    }
+}
 
-        // Finds removed variables and generates null entries
-        old_env
-            .iter()
-            .filter(|(key, _)| !new_env.contains_key(*key))
-            .for_each(|(key, _)| {
-                changes.insert(key.to_owned(), None);
-            });
+/// Replaces the user info of a URL, such as a proxy password, with a placeholder.
+fn redact_url_userinfo(url: &str) -> String {
+    let authority_start = url.find("://").map(|pos| pos + 3).unwrap_or(0);
+    let authority_end = url[authority_start..]
+        .find('/')
+        .map(|pos| authority_start + pos)
+        .unwrap_or(url.len());
+    match url[authority_start..authority_end].rfind('@') {
+        Some(at) => format!(
+            "{}<redacted>{}",
+            &url[..authority_start],
+            &url[authority_start + at..]
+        ),
+        None => url.to_owned(),
     }
+}
+
+fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Reads the content of an input file, which can either be a local path or an HTTP(S) URL.
+fn read_input_file(client: &Client, location: &str) -> Result<String> {
+    if location == "-" {
+        Ok(std::io::read_to_string(std::io::stdin())?)
+    } else if is_url(location) {
+        let response = client.get(location).send()?;
+
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("unable to download {location}: HTTP status {status}");
+        }
 
-    CloudflarePagesEnvironment {
-        env_vars: Some(changes),
+        Ok(response.text()?)
+    } else {
+        Ok(std::fs::read_to_string(location)?)
     }
 }
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{EnvVarsFile, Environment};
+
+/// Netlify context whose variables correspond to the Pages preview environment.
+const PREVIEW_CONTEXT: &str = "deploy-preview";
+
+/// The environment variable sections of a `netlify.toml` file. Other sections are ignored when
+/// reading and never written.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetlifyConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<NetlifyEnvironmentSection>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<String, NetlifyEnvironmentSection>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetlifyEnvironmentSection {
+    #[serde(default)]
+    pub environment: BTreeMap<String, String>,
+}
+
+impl NetlifyConfig {
+    /// Resolves the variables Netlify would use for an environment: `[build.environment]`,
+    /// overridden by the `production` or `deploy-preview` context.
+    pub fn environment_vars(&self, environment: Environment) -> BTreeMap<String, String> {
+        let context = match environment {
+            Environment::Production => "production",
+            Environment::Preview => PREVIEW_CONTEXT,
+        };
+
+        let mut vars = self
+            .build
+            .as_ref()
+            .map(|build| build.environment.clone())
+            .unwrap_or_default();
+        if let Some(section) = self.context.get(context) {
+            vars.extend(section.environment.clone());
+        }
+        vars
+    }
+}
+
+impl From<&EnvVarsFile> for NetlifyConfig {
+    /// Production variables go into `[build.environment]` and preview variables into
+    /// `[context.deploy-preview.environment]`. Deletions cannot be represented and are skipped.
+    fn from(value: &EnvVarsFile) -> Self {
+        let mut config = Self::default();
+        for (environment, env) in value.environments() {
+            let section = NetlifyEnvironmentSection {
+                environment: env
+                    .iter()
+                    .filter_map(|(key, var)| {
+                        var.as_ref().map(|var| (key.clone(), var.value.clone()))
+                    })
+                    .collect(),
+            };
+            match environment {
+                Environment::Production => config.build = Some(section),
+                Environment::Preview => {
+                    config.context.insert(PREVIEW_CONTEXT.to_owned(), section);
+                }
+            }
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{types::EnvVar, FileFormat};
+
+    #[test]
+    fn test_serialize() {
+        let vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("1".to_owned()))),
+                ("DELETED".to_owned(), None),
+            ])),
+            preview: Some(BTreeMap::from([(
+                "B".to_owned(),
+                Some(EnvVar::secret("2".to_owned())),
+            )])),
+        };
+
+        assert_eq!(
+            FileFormat::Toml
+                .serialize(&NetlifyConfig::from(&vars))
+                .unwrap(),
+            "[build.environment]\nA = \"1\"\n\n[context.deploy-preview.environment]\nB = \"2\""
+        );
+    }
+
+    #[test]
+    fn test_environment_vars() {
+        let config: NetlifyConfig = FileFormat::Toml
+            .deserialize(
+                r#"
+[build]
+command = "npm run build"
+
+[build.environment]
+A = "base"
+B = "base"
+
+[context.production.environment]
+B = "production"
+
+[context.deploy-preview.environment]
+C = "preview"
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.environment_vars(Environment::Production),
+            BTreeMap::from([
+                ("A".to_owned(), "base".to_owned()),
+                ("B".to_owned(), "production".to_owned()),
+            ])
+        );
+        assert_eq!(
+            config.environment_vars(Environment::Preview),
+            BTreeMap::from([
+                ("A".to_owned(), "base".to_owned()),
+                ("B".to_owned(), "base".to_owned()),
+                ("C".to_owned(), "preview".to_owned()),
+            ])
+        );
+    }
+}
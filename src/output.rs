@@ -0,0 +1,154 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::ErrorKind,
+    path::Path,
+};
+
+use anyhow::Result;
+
+/// Permissions applied by default to output files that may contain secret values.
+pub const SECRET_FILE_MODE: u32 = 0o600;
+
+/// Parses Unix permission bits given in octal, such as `600`, `0600` or `0o600`.
+pub fn parse_file_mode(value: &str) -> Result<u32> {
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if !digits.is_empty() && mode <= 0o777 => Ok(mode),
+        _ => anyhow::bail!("invalid file mode {value:?}: expected octal permissions like 600"),
+    }
+}
+
+/// Makes sure the parent directory of an output file exists, creating it and any missing ancestors
+/// if `create` is set. Otherwise, a missing directory is reported by name, rather than as the
+/// bare OS error that creating the file would fail with.
+pub fn ensure_parent_dir(path: &Path, create: bool) -> Result<()> {
+    let parent = match path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        Some(parent) => parent,
+        None => return Ok(()),
+    };
+
+    if !parent.exists() {
+        if !create {
+            anyhow::bail!(
+                "directory {} does not exist. Pass --create-missing-dirs to create it",
+                parent.display()
+            );
+        }
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+/// Creates or truncates a file for writing output. Unless `overwrite` is set, an existing file is
+/// left untouched and reported as an error instead. On Unix, `mode` sets the permission bits of
+/// the file, including when it already exists. Permissions are left untouched on other platforms.
+pub fn create_output_file(path: &Path, mode: Option<u32>, overwrite: bool) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        // Checked by the OS when creating the file, so that a file appearing in the meantime is
+        // never clobbered either
+        options.create_new(true);
+    }
+    let open = |options: &OpenOptions| match options.open(path) {
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => anyhow::bail!(
+            "{} already exists. Pass --force to overwrite it",
+            path.display()
+        ),
+        result => Ok(result?),
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        if let Some(mode) = mode {
+            // New files are created with the mode right away, so that they are never readable by
+            // others, even briefly
+            options.mode(mode);
+        }
+        let file = open(&options)?;
+        if let Some(mode) = mode {
+            file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+        }
+        Ok(file)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        open(&options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_mode() {
+        assert_eq!(parse_file_mode("600").unwrap(), 0o600);
+        assert_eq!(parse_file_mode("0640").unwrap(), 0o640);
+        assert_eq!(parse_file_mode("0o644").unwrap(), 0o644);
+        assert!(parse_file_mode("").is_err());
+        assert!(parse_file_mode("800").is_err());
+        assert!(parse_file_mode("1777").is_err());
+        assert!(parse_file_mode("rw").is_err());
+    }
+
+    #[test]
+    fn test_ensure_parent_dir() {
+        let dir = std::env::temp_dir().join(format!("cf-pages-dirs-{}", std::process::id()));
+        let path = dir.join("nested").join("vars.json");
+
+        let err = ensure_parent_dir(&path, false).unwrap_err();
+        assert!(err.to_string().contains("nested"));
+        ensure_parent_dir(&path, true).unwrap();
+        let created = path.parent().unwrap().is_dir();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(created);
+        assert!(ensure_parent_dir(Path::new("vars.json"), false).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_output_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("cf-pages-output-{}.env", std::process::id()));
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let file = create_output_file(&path, Some(0o600), true).unwrap();
+        let mode = file.metadata().unwrap().permissions().mode();
+        drop(file);
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(content, "");
+    }
+
+    #[test]
+    fn test_create_output_file_no_overwrite() {
+        let path =
+            std::env::temp_dir().join(format!("cf-pages-clobber-{}.env", std::process::id()));
+        std::fs::write(&path, "hand-edited").unwrap();
+
+        let err = create_output_file(&path, None, false).unwrap_err();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let created = create_output_file(&path, None, false).is_ok();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(err.to_string().contains("--force"));
+        assert_eq!(content, "hand-edited");
+        assert!(created);
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+
+use crate::types::{
+    CloudflarePagesDeploymentConfigs, CloudflarePagesEnvVarValue, CloudflarePagesEnvironment,
+    EnvVar, EnvVarsFile, Environment, FullEnvVarsFile,
+};
+
+/// How variables missing from the desired set are treated. Variables explicitly set to `null` are
+/// deleted in both modes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PatchMode {
+    /// Variables missing from an environment in the file are deleted.
+    #[default]
+    Replace,
+    /// Variables missing from an environment in the file are left untouched.
+    Merge,
+}
+
+/// Part of a patch to apply, for splitting a change into stages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchEntryFilter {
+    /// Only added and changed variables.
+    Additions,
+    /// Only deleted variables.
+    Deletions,
+}
+
+/// Computes the minimal patch turning the existing variables into the desired ones.
+pub fn generate_deployment_configs_patch(
+    old_vars: &FullEnvVarsFile,
+    new_vars: &EnvVarsFile,
+    mode: PatchMode,
+) -> CloudflarePagesDeploymentConfigs {
+    CloudflarePagesDeploymentConfigs {
+        preview: generate_env_patch(&old_vars.preview, &new_vars.preview, mode),
+        production: generate_env_patch(&old_vars.production, &new_vars.production, mode),
+    }
+}
+
+/// Computes the minimal patch for a single environment. Returns an empty patch with no `env_vars`
+/// when the environment is absent from the desired variables.
+pub fn generate_env_patch(
+    old_env: &BTreeMap<String, EnvVar>,
+    new_env: &Option<BTreeMap<String, Option<EnvVar>>>,
+    mode: PatchMode,
+) -> CloudflarePagesEnvironment {
+    let mut changes: BTreeMap<String, Option<CloudflarePagesEnvVarValue>> = Default::default();
+
+    if let Some(new_env) = new_env.as_ref() {
+        // Finds new and changed variables
+        new_env
+            .iter()
+            .filter_map(|(key, value)| value.as_ref().map(|value| (key, value)))
+            .filter(|(key, value)| match old_env.get(*key) {
+                Some(old_value) => {
+                    // Keep the patch minimal: do not generate entry if not necessary
+                    *value != old_value
+                }
+                None => {
+                    // This is a new env var
+                    true
+                }
+            })
+            .for_each(|(key, value)| {
+                changes.insert(
+                    key.to_owned(),
+                    Some(CloudflarePagesEnvVarValue {
+                        r#type: value.value_type(),
+                        value: value.value.to_owned(),
+                    }),
+                );
+            });
+
+        // Finds variables explicitly marked for deletion
+        new_env
+            .iter()
+            .filter(|(key, value)| value.is_none() && old_env.contains_key(*key))
+            .for_each(|(key, _)| {
+                changes.insert(key.to_owned(), None);
+            });
+
+        // Finds removed variables and generates null entries
+        if mode == PatchMode::Replace {
+            old_env
+                .iter()
+                .filter(|(key, _)| !new_env.contains_key(*key))
+                .for_each(|(key, _)| {
+                    changes.insert(key.to_owned(), None);
+                });
+        }
+    }
+
+    CloudflarePagesEnvironment {
+        // Environments absent from the file are left out of the patch entirely
+        env_vars: new_env.as_ref().map(|_| changes),
+    }
+}
+
+/// Splits a patch into multiple standalone patches, each containing at most `max_vars` entries
+/// across both environments. Environments without entries in a chunk are left out of it.
+pub fn split_patch(
+    patch: &CloudflarePagesDeploymentConfigs,
+    max_vars: usize,
+) -> Vec<CloudflarePagesDeploymentConfigs> {
+    let entries = [
+        (Environment::Production, &patch.production),
+        (Environment::Preview, &patch.preview),
+    ]
+    .into_iter()
+    .flat_map(|(environment, env)| {
+        env.env_vars
+            .iter()
+            .flatten()
+            .map(move |(key, value)| (environment, key, value))
+    })
+    .collect::<Vec<_>>();
+
+    entries
+        .chunks(max_vars.max(1))
+        .map(|chunk| {
+            let mut chunk_patch = CloudflarePagesDeploymentConfigs {
+                preview: CloudflarePagesEnvironment { env_vars: None },
+                production: CloudflarePagesEnvironment { env_vars: None },
+            };
+            for (environment, key, value) in chunk.iter() {
+                let env = match environment {
+                    Environment::Production => &mut chunk_patch.production,
+                    Environment::Preview => &mut chunk_patch.preview,
+                };
+                env.env_vars
+                    .get_or_insert_with(Default::default)
+                    .insert((*key).to_owned(), (*value).to_owned());
+            }
+            chunk_patch
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::vars, types::CloudflarePagesEnvVarValueType};
+
+    fn plain_text(value: &str) -> Option<CloudflarePagesEnvVarValue> {
+        Some(CloudflarePagesEnvVarValue {
+            r#type: CloudflarePagesEnvVarValueType::PlainText,
+            value: value.to_owned(),
+        })
+    }
+
+    #[test]
+    fn test_patch_serialization_is_deterministic() {
+        let old_vars = FullEnvVarsFile {
+            production: vars(&[("Z", "old"), ("M", "old"), ("A", "old")]),
+            preview: Default::default(),
+        };
+        let render = |file: &str| {
+            let new_vars: EnvVarsFile = serde_json::from_str(file).unwrap();
+            serde_json::to_string(&generate_deployment_configs_patch(
+                &old_vars,
+                &new_vars,
+                PatchMode::Replace,
+            ))
+            .unwrap()
+        };
+
+        let first =
+            render(r#"{"production":{"Z":"1","B":"2","A":"3"},"preview":{"Y":"4","C":"5"}}"#);
+        let second =
+            render(r#"{"preview":{"C":"5","Y":"4"},"production":{"B":"2","A":"3","Z":"1"}}"#);
+
+        assert_eq!(first, second);
+        assert_eq!(
+            first,
+            r#"{"preview":{"env_vars":{"C":{"type":"plain_text","value":"5"},"Y":{"type":"plain_text","value":"4"}}},"production":{"env_vars":{"A":{"type":"plain_text","value":"3"},"B":{"type":"plain_text","value":"2"},"M":null,"Z":{"type":"plain_text","value":"1"}}}}"#
+        );
+    }
+
+    #[test]
+    fn test_generate_env_patch_addition() {
+        let patch = generate_env_patch(
+            &vars(&[("A", "1")]),
+            &Some(vars(&[("A", "1"), ("B", "2")])),
+            PatchMode::Replace,
+        );
+
+        assert_eq!(
+            patch.env_vars,
+            Some(BTreeMap::from([("B".to_owned(), plain_text("2"))]))
+        );
+    }
+
+    #[test]
+    fn test_generate_env_patch_change() {
+        let patch = generate_env_patch(
+            &vars(&[("A", "1"), ("B", "2")]),
+            &Some(vars(&[("A", "1"), ("B", "3")])),
+            PatchMode::Replace,
+        );
+
+        assert_eq!(
+            patch.env_vars,
+            Some(BTreeMap::from([("B".to_owned(), plain_text("3"))]))
+        );
+    }
+
+    #[test]
+    fn test_generate_env_patch_type_change() {
+        let mut new_env: BTreeMap<String, Option<EnvVar>> = vars(&[("A", "1")]);
+        new_env.get_mut("A").unwrap().as_mut().unwrap().secret = true;
+
+        let patch = generate_env_patch(&vars(&[("A", "1")]), &Some(new_env), PatchMode::Replace);
+
+        assert_eq!(
+            patch.env_vars,
+            Some(BTreeMap::from([(
+                "A".to_owned(),
+                Some(CloudflarePagesEnvVarValue {
+                    r#type: CloudflarePagesEnvVarValueType::SecretText,
+                    value: "1".to_owned(),
+                })
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_generate_env_patch_removal() {
+        let patch = generate_env_patch(
+            &vars(&[("A", "1"), ("B", "2")]),
+            &Some(vars(&[("A", "1")])),
+            PatchMode::Replace,
+        );
+
+        assert_eq!(
+            patch.env_vars,
+            Some(BTreeMap::from([("B".to_owned(), None)]))
+        );
+    }
+
+    #[test]
+    fn test_generate_env_patch_explicit_deletion() {
+        let mut new_env: BTreeMap<String, Option<EnvVar>> = vars(&[("A", "1")]);
+        new_env.insert("B".to_owned(), None);
+        new_env.insert("MISSING".to_owned(), None);
+
+        for mode in [PatchMode::Replace, PatchMode::Merge] {
+            let patch = generate_env_patch(
+                &vars(&[("A", "1"), ("B", "2")]),
+                &Some(new_env.clone()),
+                mode,
+            );
+
+            assert_eq!(
+                patch.env_vars,
+                Some(BTreeMap::from([("B".to_owned(), None)]))
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_env_patch_merge() {
+        let patch = generate_env_patch(
+            &vars(&[("A", "1"), ("B", "2")]),
+            &Some(vars(&[("B", "3"), ("C", "4")])),
+            PatchMode::Merge,
+        );
+
+        assert_eq!(
+            patch.env_vars,
+            Some(BTreeMap::from([
+                ("B".to_owned(), plain_text("3")),
+                ("C".to_owned(), plain_text("4")),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_generate_env_patch_no_op() {
+        let patch = generate_env_patch(
+            &vars(&[("A", "1")]),
+            &Some(vars(&[("A", "1")])),
+            PatchMode::Replace,
+        );
+
+        assert_eq!(patch.env_vars, Some(BTreeMap::new()));
+    }
+
+    #[test]
+    fn test_generate_env_patch_absent_environment() {
+        let patch = generate_env_patch(&vars(&[("A", "1")]), &None, PatchMode::Replace);
+
+        assert_eq!(patch.env_vars, None);
+    }
+
+    #[test]
+    fn test_generate_deployment_configs_patch_is_empty() {
+        let old_vars = FullEnvVarsFile {
+            production: vars(&[("A", "1")]),
+            preview: vars(&[("B", "2")]),
+        };
+
+        let unchanged = generate_deployment_configs_patch(
+            &old_vars,
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1")])),
+                preview: None,
+            },
+            PatchMode::Replace,
+        );
+        assert!(unchanged.is_empty());
+
+        let changed = generate_deployment_configs_patch(
+            &old_vars,
+            &EnvVarsFile {
+                production: None,
+                preview: Some(vars(&[])),
+            },
+            PatchMode::Replace,
+        );
+        assert!(!changed.is_empty());
+    }
+
+    #[test]
+    fn test_split_patch() {
+        let patch = generate_deployment_configs_patch(
+            &FullEnvVarsFile {
+                production: vars(&[("REMOVE", "x")]),
+                preview: vars(&[]),
+            },
+            &EnvVarsFile {
+                production: Some(vars(&[("A", "1"), ("B", "2")])),
+                preview: Some(vars(&[("C", "3")])),
+            },
+            PatchMode::Replace,
+        );
+
+        let chunks = split_patch(&patch, 2);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].production.env_vars,
+            Some(BTreeMap::from([
+                ("A".to_owned(), plain_text("1")),
+                ("B".to_owned(), plain_text("2")),
+            ]))
+        );
+        assert_eq!(chunks[0].preview.env_vars, None);
+        assert_eq!(
+            chunks[1].production.env_vars,
+            Some(BTreeMap::from([("REMOVE".to_owned(), None)]))
+        );
+        assert_eq!(
+            chunks[1].preview.env_vars,
+            Some(BTreeMap::from([("C".to_owned(), plain_text("3"))]))
+        );
+    }
+}
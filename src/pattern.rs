@@ -0,0 +1,148 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+
+/// Matches `text` against a glob pattern, where `*` matches any sequence of characters and `?`
+/// matches a single character. All other characters match literally.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Lets the last `*` swallow one more character
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Checks whether a string contains glob wildcards.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Expands a path pattern with wildcards in any of its components into the matching files, in
+/// sorted order. Hidden entries are only matched by components starting with `.`. Fails if
+/// nothing matches.
+pub fn expand_path_glob(pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut candidates = vec![PathBuf::new()];
+    for component in Path::new(pattern).components() {
+        let component_pattern = match component {
+            Component::Normal(name) => name.to_string_lossy(),
+            _ => {
+                candidates.iter_mut().for_each(|path| path.push(component));
+                continue;
+            }
+        };
+        if !is_glob(&component_pattern) {
+            candidates.iter_mut().for_each(|path| path.push(component));
+            continue;
+        }
+
+        let mut matches = vec![];
+        for dir in candidates.iter() {
+            let dir_to_read = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            let entries = match std::fs::read_dir(dir_to_read) {
+                Ok(entries) => entries,
+                // Non-directories and unreadable directories simply have no matches
+                Err(_) => continue,
+            };
+            for entry in entries {
+                let name = entry?.file_name();
+                let name = name.to_string_lossy();
+                if name.starts_with('.') && !component_pattern.starts_with('.') {
+                    continue;
+                }
+                if glob_match(&component_pattern, &name) {
+                    matches.push(dir.join(name.as_ref()));
+                }
+            }
+        }
+        candidates = matches;
+    }
+
+    let mut files = candidates
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        anyhow::bail!("pattern {pattern} does not match any file");
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("OLD_*", "OLD_API_KEY"));
+        assert!(glob_match("OLD_*", "OLD_"));
+        assert!(!glob_match("OLD_*", "NEW_OLD_KEY"));
+        assert!(glob_match("*_URL", "API_URL"));
+        assert!(glob_match("*_KEY_*", "API_KEY_V2"));
+        assert!(glob_match("A?C", "ABC"));
+        assert!(!glob_match("A?C", "AC"));
+        assert!(glob_match("EXACT", "EXACT"));
+        assert!(!glob_match("EXACT", "EXACTLY"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn test_expand_path_glob() {
+        let dir = std::env::temp_dir().join(format!("cf-pages-glob-{}", std::process::id()));
+        for file in [
+            "a/1.json",
+            "a/2.json",
+            "a/.hidden.json",
+            "a/notes.txt",
+            "b/3.json",
+        ] {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "{}").unwrap();
+        }
+        let base = dir.to_str().unwrap();
+
+        let files = expand_path_glob(&format!("{base}/*/*.json"));
+        let missing = expand_path_glob(&format!("{base}/*/*.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            files.unwrap(),
+            vec![
+                dir.join("a/1.json"),
+                dir.join("a/2.json"),
+                dir.join("b/3.json")
+            ]
+        );
+        assert!(missing.is_err());
+    }
+}
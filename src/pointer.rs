@@ -0,0 +1,79 @@
+use anyhow::Result;
+use serde_json::Value;
+
+/// Parses `value` as JSON and extracts the field at `pointer`, a JSON pointer such as `/db/host`.
+/// Strings are returned as-is, while other values are returned as compact JSON.
+pub fn extract_json_pointer(value: &str, pointer: &str) -> Result<String> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        anyhow::bail!("invalid JSON pointer {pointer:?}: must be empty or start with /");
+    }
+
+    let json: Value = serde_json::from_str(value)
+        .map_err(|err| anyhow::anyhow!("value is not valid JSON: {err}"))?;
+    match json.pointer(pointer) {
+        Some(Value::String(field)) => Ok(field.to_owned()),
+        Some(field) => Ok(field.to_string()),
+        None => anyhow::bail!("JSON pointer {pointer:?} does not match any field"),
+    }
+}
+
+/// Keeps only the given top-level fields of a JSON object, in the given order.
+pub fn select_fields(value: &Value, fields: &[String]) -> Result<Value> {
+    let object = match value {
+        Value::Object(object) => object,
+        _ => anyhow::bail!("value is not a JSON object"),
+    };
+
+    let mut selected = serde_json::Map::new();
+    for field in fields.iter() {
+        match object.get(field) {
+            Some(field_value) => {
+                selected.insert(field.to_owned(), field_value.to_owned());
+            }
+            None => anyhow::bail!(
+                "unknown field {field:?}, expected one of: {}",
+                object.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+    Ok(Value::Object(selected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUE: &str = r#"{"db":{"host":"localhost","port":5432,"tags":["a","b"]}}"#;
+
+    #[test]
+    fn test_select_fields() {
+        let value = serde_json::json!({ "id": "1", "name": "site", "deployment_configs": {} });
+
+        assert_eq!(
+            select_fields(&value, &["name".to_owned(), "id".to_owned()]).unwrap(),
+            serde_json::json!({ "name": "site", "id": "1" })
+        );
+        assert!(select_fields(&value, &["missing".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn test_extract_json_pointer() {
+        assert_eq!(
+            extract_json_pointer(VALUE, "/db/host").unwrap(),
+            "localhost"
+        );
+        assert_eq!(extract_json_pointer(VALUE, "/db/port").unwrap(), "5432");
+        assert_eq!(extract_json_pointer(VALUE, "/db/tags/1").unwrap(), "b");
+        assert_eq!(
+            extract_json_pointer(VALUE, "/db/tags").unwrap(),
+            r#"["a","b"]"#
+        );
+    }
+
+    #[test]
+    fn test_extract_json_pointer_errors() {
+        assert!(extract_json_pointer(VALUE, "/db/user").is_err());
+        assert!(extract_json_pointer(VALUE, "db/host").is_err());
+        assert!(extract_json_pointer("not json", "/db").is_err());
+    }
+}
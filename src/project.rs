@@ -0,0 +1,56 @@
+use anyhow::Result;
+
+use crate::{
+    client::CloudflareApi, error::CfPagesError, types::CloudflarePagesCreateProjectRequest,
+};
+
+/// Creates the project with `production_branch` if it does not exist yet. Returns whether the
+/// project was created.
+pub fn ensure_project<A>(api: &A, project: &str, production_branch: &str) -> Result<bool>
+where
+    A: CloudflareApi,
+{
+    match api.get_project(project) {
+        Ok(_) => Ok(false),
+        Err(err)
+            if matches!(
+                err.downcast_ref(),
+                Some(CfPagesError::ProjectNotFound { .. })
+            ) =>
+        {
+            api.create_project(&CloudflarePagesCreateProjectRequest {
+                name: project.to_owned(),
+                production_branch: production_branch.to_owned(),
+            })?;
+            Ok(true)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::FakeApi;
+
+    #[test]
+    fn test_ensure_project_existing() {
+        let api = FakeApi::new("my-project", Default::default(), Default::default());
+
+        assert!(!ensure_project(&api, "my-project", "main").unwrap());
+    }
+
+    #[test]
+    fn test_ensure_project_missing() {
+        let api = FakeApi::new("other-project", Default::default(), Default::default());
+
+        let err = api.get_project("my-project").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "project 'my-project' not found in account 'fake-account'"
+        );
+
+        assert!(ensure_project(&api, "my-project", "main").unwrap());
+        assert_eq!(api.get_project("my-project").unwrap().name, "my-project");
+    }
+}
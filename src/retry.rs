@@ -0,0 +1,107 @@
+use std::{ops::BitOr, str::FromStr};
+
+use crate::error::CfPagesError;
+
+/// Set of failure classes that trigger a retry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryOn(u8);
+
+impl RetryOn {
+    pub const NONE: Self = Self(0);
+    /// Requests that timed out, which may or may not have been processed.
+    pub const TIMEOUT: Self = Self(1);
+    /// Requests that could not connect, and thus never reached Cloudflare.
+    pub const CONNECT: Self = Self(1 << 1);
+    /// Requests failing with HTTP 5xx.
+    pub const SERVER_ERROR: Self = Self(1 << 2);
+    /// Requests rejected with HTTP 429.
+    pub const RATE_LIMITED: Self = Self(1 << 3);
+    pub const ALL: Self = Self(0b1111);
+
+    /// Default for requests changing state, which are only retried if they certainly did not go
+    /// through.
+    pub const PATCH_DEFAULT: Self = Self::CONNECT;
+    /// Default for read-only requests, which are always safe to retry.
+    pub const GET_DEFAULT: Self = Self::ALL;
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Checks whether an error, or any error in its chain, belongs to one of the classes.
+    pub fn matches(self, error: &anyhow::Error) -> bool {
+        let class = match CfPagesError::find(error) {
+            Some(CfPagesError::Timeout(_)) => Self::TIMEOUT,
+            Some(CfPagesError::Connect(_)) => Self::CONNECT,
+            Some(CfPagesError::ServerError { .. }) => Self::SERVER_ERROR,
+            Some(CfPagesError::RateLimited) => Self::RATE_LIMITED,
+            _ => return false,
+        };
+        self.contains(class)
+    }
+}
+
+impl BitOr for RetryOn {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl FromStr for RetryOn {
+    type Err = anyhow::Error;
+
+    /// Parses a comma-separated list of `timeout`, `connect`, `5xx` and `429`. `none` disables
+    /// retries.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .try_fold(Self::NONE, |set, item| {
+                let class = match item {
+                    "timeout" => Self::TIMEOUT,
+                    "connect" => Self::CONNECT,
+                    "5xx" => Self::SERVER_ERROR,
+                    "429" => Self::RATE_LIMITED,
+                    "none" => Self::NONE,
+                    _ => anyhow::bail!(
+                        "unknown retry condition {item:?}, expected timeout, connect, 5xx, 429 or \
+                        none"
+                    ),
+                };
+                Ok(set | class)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "timeout, 5xx".parse::<RetryOn>().unwrap(),
+            RetryOn::TIMEOUT | RetryOn::SERVER_ERROR
+        );
+        assert_eq!(
+            "connect,timeout,5xx,429".parse::<RetryOn>().unwrap(),
+            RetryOn::ALL
+        );
+        assert_eq!("none".parse::<RetryOn>().unwrap(), RetryOn::NONE);
+        assert!("4xx".parse::<RetryOn>().is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let retry_on = RetryOn::CONNECT | RetryOn::RATE_LIMITED;
+
+        assert!(retry_on.matches(&CfPagesError::Connect("refused".to_owned()).into()));
+        assert!(retry_on.matches(&CfPagesError::RateLimited.into()));
+        assert!(!retry_on.matches(&CfPagesError::Timeout("timeout".to_owned()).into()));
+        assert!(!retry_on.matches(&CfPagesError::ServerError { status: 502 }.into()));
+        assert!(!RetryOn::ALL.matches(&CfPagesError::Unauthorized { status: 401 }.into()));
+        assert!(!RetryOn::ALL.matches(&anyhow::anyhow!("other")));
+    }
+}
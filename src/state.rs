@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Hashes the JSON form of `state` into a hex string. Maps must have sorted keys (e.g.
+/// `BTreeMap`) for the result to be stable. FNV-1a is used instead of the standard library hasher,
+/// whose output may change between Rust versions.
+pub fn state_hash<T>(state: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    let json = serde_json::to_vec(state)?;
+    let hash = json.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    });
+
+    Ok(format!("{hash:016x}"))
+}
+
+/// Reads the hash recorded in a state file, returning `None` if the file does not exist yet.
+pub fn read_state_hash(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content.trim().to_owned())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => {
+            Err(err).with_context(|| format!("unable to read state file {}", path.display()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_state_hash_stable() {
+        // FNV-1a of `""` (the JSON form of an empty string)
+        assert_eq!(state_hash(&"").unwrap(), "07cc7607b4949e25");
+
+        let forward = BTreeMap::from([("A", "1"), ("B", "2")]);
+        let backward = BTreeMap::from([("B", "2"), ("A", "1")]);
+        assert_eq!(
+            state_hash(&forward).unwrap(),
+            state_hash(&backward).unwrap()
+        );
+        assert_ne!(
+            state_hash(&forward).unwrap(),
+            state_hash(&BTreeMap::from([("A", "1"), ("B", "3")])).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_missing_state_file() {
+        let path = std::env::temp_dir().join("cf-pages-test-missing-state");
+
+        assert_eq!(read_state_hash(&path).unwrap(), None);
+    }
+}
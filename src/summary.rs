@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    CloudflarePagesDeploymentConfigs, CloudflarePagesEnvironment, EnvVar, FullEnvVarsFile,
+};
+
+/// Human-oriented breakdown of the changes a patch makes to both environments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeSummary {
+    pub production: EnvChanges,
+    pub preview: EnvChanges,
+}
+
+/// Keys added, changed and removed by a patch in a single environment.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvChanges {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ChangeSummary {
+    pub fn from_patch(
+        old_vars: &FullEnvVarsFile,
+        patch: &CloudflarePagesDeploymentConfigs,
+    ) -> Self {
+        Self {
+            production: EnvChanges::from_patch(&old_vars.production, &patch.production),
+            preview: EnvChanges::from_patch(&old_vars.preview, &patch.preview),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.production.is_empty() && self.preview.is_empty()
+    }
+
+    /// Adds the changes of `other`, e.g. of another request applying part of the same update.
+    pub fn merge(&mut self, other: Self) {
+        self.production.merge(other.production);
+        self.preview.merge(other.preview);
+    }
+
+    /// Drops the changes to keys that `patch` still has to apply.
+    pub fn without_keys_in(mut self, patch: &CloudflarePagesDeploymentConfigs) -> Self {
+        self.production.retain_keys_not_in(&patch.production);
+        self.preview.retain_keys_not_in(&patch.preview);
+        self
+    }
+
+    /// Drops value changes, keeping only keys that are added or removed.
+    pub fn keys_only(mut self) -> Self {
+        self.production.changed.clear();
+        self.preview.changed.clear();
+        self
+    }
+}
+
+impl EnvChanges {
+    pub fn from_patch(
+        old_env: &BTreeMap<String, EnvVar>,
+        patch: &CloudflarePagesEnvironment,
+    ) -> Self {
+        let mut changes = Self::default();
+
+        for (key, value) in patch.env_vars.iter().flatten() {
+            match (value, old_env.contains_key(key)) {
+                (Some(_), false) => changes.added.push(key.to_owned()),
+                (Some(_), true) => changes.changed.push(key.to_owned()),
+                (None, _) => changes.removed.push(key.to_owned()),
+            }
+        }
+
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (keys, other_keys) in [
+            (&mut self.added, other.added),
+            (&mut self.changed, other.changed),
+            (&mut self.removed, other.removed),
+        ] {
+            keys.extend(other_keys);
+            keys.sort();
+            keys.dedup();
+        }
+    }
+
+    fn retain_keys_not_in(&mut self, patch: &CloudflarePagesEnvironment) {
+        let pending = |key: &String| {
+            patch
+                .env_vars
+                .as_ref()
+                .is_some_and(|env_vars| env_vars.contains_key(key))
+        };
+        for keys in [&mut self.added, &mut self.changed, &mut self.removed] {
+            keys.retain(|key| !pending(key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_deployment_configs_patch, test_utils::vars, EnvVarsFile, PatchMode};
+
+    #[test]
+    fn test_change_summary_from_patch() {
+        let old_vars = FullEnvVarsFile {
+            production: vars(&[("KEEP", "1"), ("CHANGE", "old"), ("REMOVE", "x")]),
+            preview: vars(&[("A", "1")]),
+        };
+        let patch = generate_deployment_configs_patch(
+            &old_vars,
+            &EnvVarsFile {
+                production: Some(vars(&[("KEEP", "1"), ("CHANGE", "new"), ("ADD", "2")])),
+                preview: None,
+            },
+            PatchMode::Replace,
+        );
+
+        let summary = ChangeSummary::from_patch(&old_vars, &patch);
+
+        assert_eq!(
+            summary.production,
+            EnvChanges {
+                added: vec!["ADD".to_owned()],
+                changed: vec!["CHANGE".to_owned()],
+                removed: vec!["REMOVE".to_owned()],
+            }
+        );
+        assert!(summary.preview.is_empty());
+        assert!(!summary.is_empty());
+
+        let summary = summary.keys_only();
+        assert!(summary.production.changed.is_empty());
+        assert_eq!(summary.production.added, vec!["ADD".to_owned()]);
+        assert_eq!(summary.production.removed, vec!["REMOVE".to_owned()]);
+    }
+}
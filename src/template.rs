@@ -0,0 +1,107 @@
+use std::{path::PathBuf, str::FromStr};
+
+use crate::types::Environment;
+
+const PROJECT_PLACEHOLDER: &str = "{project}";
+const ENVIRONMENT_PLACEHOLDER: &str = "{environment}";
+
+/// A file path which may contain `{project}` and `{environment}` placeholders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    template: String,
+}
+
+impl PathTemplate {
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    pub fn has_project(&self) -> bool {
+        self.template.contains(PROJECT_PLACEHOLDER)
+    }
+
+    pub fn has_environment(&self) -> bool {
+        self.template.contains(ENVIRONMENT_PLACEHOLDER)
+    }
+
+    pub fn is_templated(&self) -> bool {
+        self.has_project() || self.has_environment()
+    }
+
+    /// Substitutes the placeholders. `{environment}` is left untouched if `environment` is `None`.
+    pub fn render(&self, project: &str, environment: Option<Environment>) -> PathBuf {
+        let path = self.template.replace(PROJECT_PLACEHOLDER, project);
+        match environment {
+            Some(environment) => path
+                .replace(ENVIRONMENT_PLACEHOLDER, &environment.to_string())
+                .into(),
+            None => path.into(),
+        }
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s;
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                anyhow::bail!("unmatched '}}' in path template");
+            }
+
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => anyhow::bail!("unmatched '{{' in path template"),
+            };
+            let placeholder = &rest[start..=end];
+            if placeholder != PROJECT_PLACEHOLDER && placeholder != ENVIRONMENT_PLACEHOLDER {
+                anyhow::bail!(
+                    "unknown placeholder {placeholder}. Supported placeholders: \
+                    {PROJECT_PLACEHOLDER}, {ENVIRONMENT_PLACEHOLDER}"
+                );
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        Ok(Self {
+            template: s.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template: PathTemplate = "out/{project}-{environment}.json".parse().unwrap();
+
+        assert!(template.has_project());
+        assert!(template.has_environment());
+        assert_eq!(
+            template.render("my-project", Some(Environment::Preview)),
+            PathBuf::from("out/my-project-preview.json")
+        );
+    }
+
+    #[test]
+    fn test_plain_path() {
+        let template: PathTemplate = "vars.json".parse().unwrap();
+
+        assert!(!template.is_templated());
+        assert_eq!(
+            template.render("my-project", None),
+            PathBuf::from("vars.json")
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!("{projects}.json".parse::<PathTemplate>().is_err());
+        assert!("{project.json".parse::<PathTemplate>().is_err());
+        assert!("project}.json".parse::<PathTemplate>().is_err());
+    }
+}
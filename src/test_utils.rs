@@ -0,0 +1,231 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+use anyhow::Result;
+
+use crate::{
+    client::CloudflareApi,
+    error::CfPagesError,
+    types::{
+        CloudflarePagesCreateProjectRequest, CloudflarePagesDeployment,
+        CloudflarePagesDeploymentConfigs, CloudflarePagesEnvVarValue,
+        CloudflarePagesEnvVarValueType, CloudflarePagesEnvironment, CloudflarePagesPatchRequest,
+        CloudflarePagesProject, EnvVar, Environment, ListPage,
+    },
+};
+
+/// In-memory stand-in for the Cloudflare API hosting a single project.
+pub struct FakeApi {
+    project: RefCell<CloudflarePagesProject>,
+    deployments: BTreeMap<String, CloudflarePagesDeployment>,
+    patch_count: RefCell<usize>,
+    get_count: RefCell<usize>,
+    lost_responses: RefCell<usize>,
+}
+
+impl FakeApi {
+    pub fn new(
+        name: &str,
+        production: BTreeMap<String, EnvVar>,
+        preview: BTreeMap<String, EnvVar>,
+    ) -> Self {
+        Self {
+            project: RefCell::new(CloudflarePagesProject {
+                id: format!("{name}-id"),
+                name: name.to_owned(),
+                deployment_configs: CloudflarePagesDeploymentConfigs {
+                    preview: remote_env(preview),
+                    production: remote_env(production),
+                },
+            }),
+            deployments: Default::default(),
+            patch_count: RefCell::new(0),
+            get_count: RefCell::new(0),
+            lost_responses: RefCell::new(0),
+        }
+    }
+
+    pub fn with_deployment(
+        mut self,
+        id: &str,
+        environment: Environment,
+        created_on: &str,
+        vars: BTreeMap<String, EnvVar>,
+    ) -> Self {
+        self.deployments.insert(
+            id.to_owned(),
+            CloudflarePagesDeployment {
+                id: id.to_owned(),
+                environment,
+                created_on: created_on.to_owned(),
+                vars: remote_env(vars),
+            },
+        );
+        self
+    }
+
+    /// Makes the next `count` patches fail with a transient error after being applied, as if
+    /// their responses were lost.
+    pub fn with_lost_responses(self, count: usize) -> Self {
+        *self.lost_responses.borrow_mut() = count;
+        self
+    }
+
+    pub fn patch_count(&self) -> usize {
+        *self.patch_count.borrow()
+    }
+
+    /// Number of times the project was fetched.
+    pub fn get_count(&self) -> usize {
+        *self.get_count.borrow()
+    }
+
+    fn check_project(&self, project: &str) -> Result<()> {
+        if self.project.borrow().name != project {
+            return Err(CfPagesError::ProjectNotFound {
+                project: project.to_owned(),
+                account: "fake-account".to_owned(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl CloudflareApi for FakeApi {
+    fn account(&self) -> &str {
+        "fake-account"
+    }
+
+    fn get_project(&self, project: &str) -> Result<CloudflarePagesProject> {
+        self.check_project(project)?;
+        *self.get_count.borrow_mut() += 1;
+        Ok(self.project.borrow().clone())
+    }
+
+    fn get_deployment(&self, project: &str, deployment: &str) -> Result<CloudflarePagesDeployment> {
+        self.check_project(project)?;
+        match self.deployments.get(deployment) {
+            Some(deployment) => Ok(deployment.clone()),
+            None => anyhow::bail!("deployment not found: {deployment}"),
+        }
+    }
+
+    /// Pages through deployments newest first, 25 per page by default like Cloudflare.
+    fn list_deployments(
+        &self,
+        project: &str,
+        page: usize,
+        per_page: Option<usize>,
+    ) -> Result<ListPage<CloudflarePagesDeployment>> {
+        self.check_project(project)?;
+
+        let per_page = per_page.unwrap_or(25);
+        let mut deployments = self.deployments.values().cloned().collect::<Vec<_>>();
+        deployments.sort_by(|a, b| b.created_on.cmp(&a.created_on));
+        Ok(ListPage {
+            total_count: Some(deployments.len()),
+            items: deployments
+                .into_iter()
+                .skip((page - 1) * per_page)
+                .take(per_page)
+                .collect(),
+        })
+    }
+
+    fn patch_project(
+        &self,
+        project: &str,
+        patch: &CloudflarePagesPatchRequest,
+    ) -> Result<Option<CloudflarePagesProject>> {
+        self.check_project(project)?;
+        *self.patch_count.borrow_mut() += 1;
+
+        // Cloudflare merges `env_vars`, with `null` entries deleting the variable
+        let mut stored = self.project.borrow_mut();
+        let configs = &mut stored.deployment_configs;
+        for (stored_env, patch_env) in [
+            (
+                &mut configs.production,
+                &patch.deployment_configs.production,
+            ),
+            (&mut configs.preview, &patch.deployment_configs.preview),
+        ] {
+            let stored_vars = stored_env.env_vars.get_or_insert_with(Default::default);
+            for (key, value) in patch_env.env_vars.iter().flatten() {
+                match value {
+                    Some(value) => {
+                        stored_vars.insert(key.to_owned(), Some(hide_secret(value.to_owned())));
+                    }
+                    None => {
+                        stored_vars.remove(key);
+                    }
+                }
+            }
+        }
+
+        let mut lost_responses = self.lost_responses.borrow_mut();
+        if *lost_responses > 0 {
+            *lost_responses -= 1;
+            return Err(CfPagesError::Timeout("response lost".to_owned()).into());
+        }
+
+        Ok(Some(stored.clone()))
+    }
+
+    /// Replaces the hosted project, as the fake only holds one at a time.
+    fn create_project(
+        &self,
+        request: &CloudflarePagesCreateProjectRequest,
+    ) -> Result<CloudflarePagesProject> {
+        let project = CloudflarePagesProject {
+            id: format!("{}-id", request.name),
+            name: request.name.to_owned(),
+            deployment_configs: CloudflarePagesDeploymentConfigs {
+                preview: remote_env(Default::default()),
+                production: remote_env(Default::default()),
+            },
+        };
+        *self.project.borrow_mut() = project.clone();
+        Ok(project)
+    }
+}
+
+/// Builds plain text variables from key-value pairs, as either local or remote values.
+pub fn vars<V>(entries: &[(&str, &str)]) -> BTreeMap<String, V>
+where
+    V: From<EnvVar>,
+{
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), EnvVar::plain(value.to_string()).into()))
+        .collect()
+}
+
+/// Builds an environment the way Cloudflare returns it, with secret values hidden.
+pub fn remote_env(vars: BTreeMap<String, EnvVar>) -> CloudflarePagesEnvironment {
+    CloudflarePagesEnvironment {
+        env_vars: Some(
+            vars.into_iter()
+                .map(|(key, var)| {
+                    (
+                        key,
+                        Some(hide_secret(CloudflarePagesEnvVarValue {
+                            r#type: var.value_type(),
+                            value: var.value,
+                        })),
+                    )
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn hide_secret(value: CloudflarePagesEnvVarValue) -> CloudflarePagesEnvVarValue {
+    match value.r#type {
+        CloudflarePagesEnvVarValueType::PlainText => value,
+        CloudflarePagesEnvVarValueType::SecretText => CloudflarePagesEnvVarValue {
+            r#type: CloudflarePagesEnvVarValueType::SecretText,
+            value: String::new(),
+        },
+    }
+}
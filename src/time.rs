@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+/// Parses a duration made of a whole number and one of the suffixes `s` (seconds), `m` (minutes),
+/// `h` (hours), `d` (days) or `w` (weeks), e.g. `24h` or `7d`.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split_at = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, suffix) = value.split_at(split_at);
+
+    let amount: u64 = match amount.parse() {
+        Ok(amount) => amount,
+        Err(_) => anyhow::bail!("invalid duration {value:?}: expected a number followed by a unit"),
+    };
+    let unit_secs = match suffix {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!("invalid duration {value:?}: unit must be one of s, m, h, d, w"),
+    };
+
+    match amount.checked_mul(unit_secs) {
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => anyhow::bail!("invalid duration {value:?}: too large"),
+    }
+}
+
+/// Parses an RFC 3339 timestamp such as `2023-04-25T08:30:00.123456Z` into seconds since the Unix
+/// epoch. Fractional seconds are truncated.
+pub fn parse_rfc3339(value: &str) -> Result<i64> {
+    let invalid = || anyhow::anyhow!("invalid timestamp {value:?}");
+
+    let (date, time) = value.split_once(['T', 't', ' ']).ok_or_else(invalid)?;
+    let mut date_parts = date.splitn(3, '-');
+    let next_number = |parts: &mut std::str::SplitN<'_, char>| -> Result<i64> {
+        parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(invalid)
+    };
+    let year = next_number(&mut date_parts)?;
+    let month = next_number(&mut date_parts)?;
+    let day = next_number(&mut date_parts)?;
+
+    // Splits off the UTC offset
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let sign_at = time.rfind(['+', '-']).ok_or_else(invalid)?;
+        let (time, offset) = time.split_at(sign_at);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':').ok_or_else(invalid)?;
+        let hours: i64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+        (time, sign * (hours * 3600 + minutes * 60))
+    };
+
+    let time = time.split('.').next().ok_or_else(invalid)?;
+    let mut time_parts = time.splitn(3, ':');
+    let hour = next_number(&mut time_parts)?;
+    let minute = next_number(&mut time_parts)?;
+    let second = next_number(&mut time_parts)?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return Err(invalid());
+    }
+
+    Ok(
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+            - offset_secs,
+    )
+}
+
+/// Number of days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("24h").unwrap(), Duration::from_secs(86400));
+        assert_eq!(
+            parse_duration("7d").unwrap(),
+            Duration::from_secs(7 * 86400)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(14 * 86400)
+        );
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("99999999999999999w").is_err());
+        assert!(parse_duration("7y").is_err());
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_rfc3339("2021-03-09T00:55:03.923456Z").unwrap(),
+            1615251303
+        );
+        assert_eq!(
+            parse_rfc3339("2021-03-09T08:55:03+08:00").unwrap(),
+            1615251303
+        );
+        assert_eq!(parse_rfc3339("2000-02-29T00:00:00Z").unwrap(), 951782400);
+        assert!(parse_rfc3339("2021-03-09").is_err());
+        assert!(parse_rfc3339("2021-13-09T00:00:00Z").is_err());
+    }
+}
@@ -0,0 +1,782 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use clap::{builder::PossibleValue, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+const REDACTED_VALUE: &str = "<redacted>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+    Production,
+    Preview,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflareResponse<T> {
+    /// `null` for some successful operations that have nothing to return.
+    pub result: Option<T>,
+    pub success: bool,
+    /// Paging details, only returned by list endpoints.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_info: Option<CloudflareResultInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflareResultInfo {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+    pub count: Option<usize>,
+    pub total_count: Option<usize>,
+}
+
+/// A single page of a list endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListPage<T> {
+    pub items: Vec<T>,
+    /// Number of items across all pages, if reported.
+    pub total_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflareAccount {
+    pub id: String,
+    pub name: String,
+}
+
+/// Result of verifying the token a request is authenticated with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflareTokenVerification {
+    pub id: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflareApiToken {
+    #[serde(default)]
+    pub policies: Vec<CloudflareTokenPolicy>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflareTokenPolicy {
+    pub effect: String,
+    #[serde(default)]
+    pub permission_groups: Vec<CloudflarePermissionGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePermissionGroup {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePagesProject {
+    pub id: String,
+    pub name: String,
+    pub deployment_configs: CloudflarePagesDeploymentConfigs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePagesDeployment {
+    pub id: String,
+    pub environment: Environment,
+    pub created_on: String,
+    #[serde(flatten)]
+    pub vars: CloudflarePagesEnvironment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePagesPatchRequest {
+    pub deployment_configs: CloudflarePagesDeploymentConfigs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePagesCreateProjectRequest {
+    pub name: String,
+    pub production_branch: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePagesDeploymentConfigs {
+    pub preview: CloudflarePagesEnvironment,
+    pub production: CloudflarePagesEnvironment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePagesEnvironment {
+    // A sorted map, so that patches serialize identically regardless of the order of the input,
+    // keeping request bodies and `--dry-run` output reproducible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_vars: Option<BTreeMap<String, Option<CloudflarePagesEnvVarValue>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudflarePagesEnvVarValue {
+    pub r#type: CloudflarePagesEnvVarValueType,
+    // Cloudflare never returns the values of secrets
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudflarePagesEnvVarValueType {
+    PlainText,
+    SecretText,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FullEnvVarsFile {
+    pub production: BTreeMap<String, EnvVar>,
+    pub preview: BTreeMap<String, EnvVar>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvVarsFile {
+    /// A `null` value marks the variable for deletion.
+    pub production: Option<BTreeMap<String, Option<EnvVar>>>,
+    pub preview: Option<BTreeMap<String, Option<EnvVar>>>,
+}
+
+/// A variable in a local file. Plain text variables are stored as bare strings for compatibility
+/// with files written by older versions, while secrets use the `{ "value": "...", "secret": true }`
+/// object form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVar {
+    pub value: String,
+    pub secret: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EnvVarRepr {
+    Plain(String),
+    Full {
+        value: String,
+        #[serde(default)]
+        secret: bool,
+    },
+}
+
+/// Overview of a project's environment variable setup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    pub id: String,
+    pub name: String,
+    pub production: EnvironmentInfo,
+    pub preview: EnvironmentInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    pub var_count: usize,
+    pub secrets: Vec<String>,
+}
+
+/// Sorted variable names per environment, without any values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvKeysFile {
+    pub production: Option<Vec<String>>,
+    pub preview: Option<Vec<String>>,
+}
+
+impl FromStr for Environment {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "production" => Ok(Self::Production),
+            "preview" => Ok(Self::Preview),
+            _ => Err("unknown value"),
+        }
+    }
+}
+
+impl ValueEnum for Environment {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Production, Self::Preview]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Environment::Production => Some(PossibleValue::new("production")),
+            Environment::Preview => Some(PossibleValue::new("preview")),
+        }
+    }
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Environment::Production => "production",
+                Environment::Preview => "preview",
+            }
+        )
+    }
+}
+
+impl EnvVar {
+    pub fn plain(value: String) -> Self {
+        Self {
+            value,
+            secret: false,
+        }
+    }
+
+    pub fn secret(value: String) -> Self {
+        Self {
+            value,
+            secret: true,
+        }
+    }
+
+    pub fn value_type(&self) -> CloudflarePagesEnvVarValueType {
+        if self.secret {
+            CloudflarePagesEnvVarValueType::SecretText
+        } else {
+            CloudflarePagesEnvVarValueType::PlainText
+        }
+    }
+}
+
+impl Serialize for EnvVar {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if self.secret {
+            EnvVarRepr::Full {
+                value: self.value.clone(),
+                secret: true,
+            }
+            .serialize(serializer)
+        } else {
+            serializer.serialize_str(&self.value)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EnvVar {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(match EnvVarRepr::deserialize(deserializer)? {
+            EnvVarRepr::Plain(value) => Self::plain(value),
+            EnvVarRepr::Full { value, secret } => Self { value, secret },
+        })
+    }
+}
+
+impl Serialize for Environment {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(match self {
+            Environment::Production => "production",
+            Environment::Preview => "preview",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Environment {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        match value.parse() {
+            Ok(value) => Ok(value),
+            Err(err) => Err(serde::de::Error::custom(format!(
+                "invalid environment string: {err}"
+            ))),
+        }
+    }
+}
+
+impl EnvVarsFile {
+    /// Iterates over the environments present in the file.
+    pub fn environments(
+        &self,
+    ) -> impl Iterator<Item = (Environment, &BTreeMap<String, Option<EnvVar>>)> {
+        [
+            (Environment::Production, &self.production),
+            (Environment::Preview, &self.preview),
+        ]
+        .into_iter()
+        .filter_map(|(environment, env)| env.as_ref().map(|env| (environment, env)))
+    }
+
+    /// Iterates over the entries of all present environments, skipping deletions.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut String)> {
+        [&mut self.production, &mut self.preview]
+            .into_iter()
+            .flatten()
+            .flat_map(|env| env.iter_mut())
+            .filter_map(|(key, var)| var.as_mut().map(|var| (key, &mut var.value)))
+    }
+
+    /// Iterates over the values of all present environments, skipping deletions.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Empties the values of all secrets, keeping them in the file as secrets.
+    pub fn strip_secret_values(&mut self) {
+        [&mut self.production, &mut self.preview]
+            .into_iter()
+            .flatten()
+            .flat_map(|env| env.values_mut())
+            .flatten()
+            .filter(|var| var.secret)
+            .for_each(|var| var.value.clear());
+    }
+
+    /// Replaces all values with a fixed placeholder while preserving the keys.
+    pub fn redact(&mut self) {
+        self.values_mut()
+            .for_each(|value| *value = REDACTED_VALUE.to_owned());
+    }
+
+    /// Marks the given keys as secrets in all environments, returning the keys that were not
+    /// found in any of them.
+    pub fn mark_secret<'a>(&mut self, keys: &'a [String]) -> Vec<&'a str> {
+        let mut found = vec![false; keys.len()];
+        for env in [&mut self.production, &mut self.preview]
+            .into_iter()
+            .flatten()
+        {
+            for (ind, key) in keys.iter().enumerate() {
+                if let Some(Some(var)) = env.get_mut(key) {
+                    var.secret = true;
+                    found[ind] = true;
+                }
+            }
+        }
+
+        keys.iter()
+            .zip(found)
+            .filter(|(_, found)| !found)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// Replaces missing environments with empty ones.
+    pub fn fill_missing(&mut self) {
+        self.production.get_or_insert_with(Default::default);
+        self.preview.get_or_insert_with(Default::default);
+    }
+
+    /// Treats the file as the complete set of variables, e.g. to diff against another file.
+    /// Missing environments are empty, and deletions are dropped.
+    pub fn into_full(self) -> FullEnvVarsFile {
+        let into_vars = |env: Option<BTreeMap<String, Option<EnvVar>>>| {
+            env.unwrap_or_default()
+                .into_iter()
+                .filter_map(|(key, var)| var.map(|var| (key, var)))
+                .collect()
+        };
+
+        FullEnvVarsFile {
+            production: into_vars(self.production),
+            preview: into_vars(self.preview),
+        }
+    }
+
+    /// Makes the other environment inherit all variables of `source`, with its own entries
+    /// taking precedence. An explicit deletion in the other environment removes the inherited
+    /// variable there, while variables deleted in `source` are not inherited at all.
+    pub fn inherit_from(&mut self, source: Environment) {
+        let (source_env, target_env) = match source {
+            Environment::Production => (&self.production, &mut self.preview),
+            Environment::Preview => (&self.preview, &mut self.production),
+        };
+
+        let mut inherited: BTreeMap<String, Option<EnvVar>> = source_env
+            .iter()
+            .flatten()
+            .filter(|(_, var)| var.is_some())
+            .map(|(key, var)| (key.to_owned(), var.to_owned()))
+            .collect();
+        inherited.extend(target_env.take().unwrap_or_default());
+        *target_env = Some(inherited);
+    }
+
+    /// Overlays another file on top of this one. Each environment is merged independently, with
+    /// entries from `other` (including explicit deletions) overriding existing ones.
+    pub fn merge(&mut self, other: EnvVarsFile) {
+        for (env, overlay) in [
+            (&mut self.production, other.production),
+            (&mut self.preview, other.preview),
+        ] {
+            if let Some(overlay) = overlay {
+                env.get_or_insert_with(BTreeMap::new).extend(overlay);
+            }
+        }
+    }
+
+    /// Combines both environments into one, with entries of `precedence` (including explicit
+    /// deletions) overriding those of the other environment. If only one environment is present,
+    /// it is used as-is. Returns `None` if neither is present.
+    pub fn combine(self, precedence: Environment) -> Option<BTreeMap<String, Option<EnvVar>>> {
+        let (base, overlay) = match precedence {
+            Environment::Production => (self.preview, self.production),
+            Environment::Preview => (self.production, self.preview),
+        };
+        match (base, overlay) {
+            (Some(mut base), Some(overlay)) => {
+                base.extend(overlay);
+                Some(base)
+            }
+            (base, overlay) => base.or(overlay),
+        }
+    }
+}
+
+impl From<&EnvVarsFile> for EnvKeysFile {
+    fn from(value: &EnvVarsFile) -> Self {
+        // `BTreeMap` keys are already sorted
+        Self {
+            production: value
+                .production
+                .as_ref()
+                .map(|env| env.keys().cloned().collect()),
+            preview: value
+                .preview
+                .as_ref()
+                .map(|env| env.keys().cloned().collect()),
+        }
+    }
+}
+
+impl From<CloudflarePagesDeployment> for EnvVarsFile {
+    fn from(value: CloudflarePagesDeployment) -> Self {
+        let vars = present_vars(value.vars.into());
+
+        match value.environment {
+            Environment::Production => Self {
+                production: Some(vars),
+                preview: None,
+            },
+            Environment::Preview => Self {
+                production: None,
+                preview: Some(vars),
+            },
+        }
+    }
+}
+
+impl CloudflarePagesDeploymentConfigs {
+    pub fn is_empty(&self) -> bool {
+        let is_preview_empty = match &self.preview.env_vars {
+            Some(preview) => preview.is_empty(),
+            None => true,
+        };
+        let is_production_empty = match &self.production.env_vars {
+            Some(production) => production.is_empty(),
+            None => true,
+        };
+
+        is_preview_empty && is_production_empty
+    }
+}
+
+impl From<&CloudflarePagesProject> for ProjectInfo {
+    fn from(value: &CloudflarePagesProject) -> Self {
+        Self {
+            id: value.id.clone(),
+            name: value.name.clone(),
+            production: (&value.deployment_configs.production).into(),
+            preview: (&value.deployment_configs.preview).into(),
+        }
+    }
+}
+
+impl From<&CloudflarePagesEnvironment> for EnvironmentInfo {
+    fn from(value: &CloudflarePagesEnvironment) -> Self {
+        let vars = value.env_vars.iter().flatten();
+
+        Self {
+            var_count: vars.clone().filter(|(_, value)| value.is_some()).count(),
+            secrets: vars
+                .filter(|(_, value)| {
+                    matches!(
+                        value,
+                        Some(CloudflarePagesEnvVarValue {
+                            r#type: CloudflarePagesEnvVarValueType::SecretText,
+                            ..
+                        })
+                    )
+                })
+                .map(|(key, _)| key.to_owned())
+                .collect(),
+        }
+    }
+}
+
+impl From<CloudflarePagesDeploymentConfigs> for FullEnvVarsFile {
+    fn from(value: CloudflarePagesDeploymentConfigs) -> Self {
+        Self {
+            production: value.production.into(),
+            preview: value.preview.into(),
+        }
+    }
+}
+
+impl From<CloudflarePagesDeploymentConfigs> for EnvVarsFile {
+    fn from(value: CloudflarePagesDeploymentConfigs) -> Self {
+        Self {
+            production: Some(present_vars(value.production.into())),
+            preview: Some(present_vars(value.preview.into())),
+        }
+    }
+}
+
+impl From<&CloudflarePagesEnvVarValue> for EnvVar {
+    fn from(value: &CloudflarePagesEnvVarValue) -> Self {
+        Self {
+            value: value.value.to_owned(),
+            secret: value.r#type == CloudflarePagesEnvVarValueType::SecretText,
+        }
+    }
+}
+
+impl From<CloudflarePagesEnvironment> for BTreeMap<String, EnvVar> {
+    fn from(value: CloudflarePagesEnvironment) -> Self {
+        match value.env_vars {
+            Some(env_vars) => env_vars
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        key,
+                        match value {
+                            Some(var_value) => (&var_value).into(),
+                            None => EnvVar::plain(String::new()),
+                        },
+                    )
+                })
+                .collect(),
+            None => Self::default(),
+        }
+    }
+}
+
+fn present_vars(vars: BTreeMap<String, EnvVar>) -> BTreeMap<String, Option<EnvVar>> {
+    vars.into_iter()
+        .map(|(key, var)| (key, Some(var)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_secret_values() {
+        let mut vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("PLAIN".to_owned(), Some(EnvVar::plain("1".to_owned()))),
+                (
+                    "SECRET".to_owned(),
+                    Some(EnvVar::secret("hunter2".to_owned())),
+                ),
+                ("DELETED".to_owned(), None),
+            ])),
+            preview: None,
+        };
+
+        vars.strip_secret_values();
+
+        assert_eq!(
+            vars.production,
+            Some(BTreeMap::from([
+                ("PLAIN".to_owned(), Some(EnvVar::plain("1".to_owned()))),
+                ("SECRET".to_owned(), Some(EnvVar::secret(String::new()))),
+                ("DELETED".to_owned(), None),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_mark_secret() {
+        let mut vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("API_KEY".to_owned(), Some(EnvVar::plain("1".to_owned()))),
+                ("URL".to_owned(), Some(EnvVar::plain("2".to_owned()))),
+            ])),
+            preview: Some(BTreeMap::from([("API_KEY".to_owned(), None)])),
+        };
+
+        let keys = ["API_KEY".to_owned(), "MISSING".to_owned()];
+        let missing = vars.mark_secret(&keys);
+
+        assert_eq!(missing, vec!["MISSING"]);
+        assert_eq!(
+            vars.production,
+            Some(BTreeMap::from([
+                ("API_KEY".to_owned(), Some(EnvVar::secret("1".to_owned()))),
+                ("URL".to_owned(), Some(EnvVar::plain("2".to_owned()))),
+            ]))
+        );
+        assert_eq!(
+            vars.preview,
+            Some(BTreeMap::from([("API_KEY".to_owned(), None)]))
+        );
+    }
+
+    #[test]
+    fn test_inherit_from() {
+        let mut vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("SHARED".to_owned(), Some(EnvVar::plain("prod".to_owned()))),
+                (
+                    "OVERRIDDEN".to_owned(),
+                    Some(EnvVar::plain("prod".to_owned())),
+                ),
+                (
+                    "PROD_ONLY".to_owned(),
+                    Some(EnvVar::secret("prod".to_owned())),
+                ),
+                ("DELETED".to_owned(), None),
+            ])),
+            preview: Some(BTreeMap::from([
+                (
+                    "OVERRIDDEN".to_owned(),
+                    Some(EnvVar::plain("preview".to_owned())),
+                ),
+                ("PROD_ONLY".to_owned(), None),
+            ])),
+        };
+
+        vars.inherit_from(Environment::Production);
+
+        assert_eq!(
+            vars.preview,
+            Some(BTreeMap::from([
+                ("SHARED".to_owned(), Some(EnvVar::plain("prod".to_owned()))),
+                (
+                    "OVERRIDDEN".to_owned(),
+                    Some(EnvVar::plain("preview".to_owned()))
+                ),
+                ("PROD_ONLY".to_owned(), None),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_combine() {
+        let vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("prod".to_owned()))),
+                ("B".to_owned(), Some(EnvVar::plain("prod".to_owned()))),
+            ])),
+            preview: Some(BTreeMap::from([
+                ("B".to_owned(), Some(EnvVar::plain("preview".to_owned()))),
+                ("C".to_owned(), Some(EnvVar::plain("preview".to_owned()))),
+            ])),
+        };
+
+        assert_eq!(
+            vars.clone().combine(Environment::Preview),
+            Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("prod".to_owned()))),
+                ("B".to_owned(), Some(EnvVar::plain("preview".to_owned()))),
+                ("C".to_owned(), Some(EnvVar::plain("preview".to_owned()))),
+            ]))
+        );
+        assert_eq!(
+            vars.clone()
+                .combine(Environment::Production)
+                .unwrap()
+                .get("B"),
+            Some(&Some(EnvVar::plain("prod".to_owned())))
+        );
+
+        let preview_only = EnvVarsFile {
+            production: None,
+            ..vars
+        };
+        assert_eq!(
+            preview_only.clone().combine(Environment::Production),
+            preview_only.preview
+        );
+        assert_eq!(EnvVarsFile::default().combine(Environment::Preview), None);
+    }
+
+    #[test]
+    fn test_fill_missing() {
+        let production = BTreeMap::from([("A".to_owned(), Some(EnvVar::plain("1".to_owned())))]);
+        let mut vars = EnvVarsFile {
+            production: Some(production.clone()),
+            preview: None,
+        };
+
+        vars.fill_missing();
+
+        assert_eq!(vars.production, Some(production));
+        assert_eq!(vars.preview, Some(BTreeMap::new()));
+    }
+
+    #[test]
+    fn test_into_full() {
+        let vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("1".to_owned()))),
+                ("B".to_owned(), None),
+            ])),
+            preview: None,
+        };
+
+        assert_eq!(
+            vars.into_full(),
+            FullEnvVarsFile {
+                production: BTreeMap::from([("A".to_owned(), EnvVar::plain("1".to_owned()))]),
+                preview: BTreeMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("base".to_owned()))),
+                ("B".to_owned(), Some(EnvVar::plain("base".to_owned()))),
+                ("C".to_owned(), Some(EnvVar::plain("base".to_owned()))),
+            ])),
+            preview: None,
+        };
+
+        vars.merge(EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("B".to_owned(), Some(EnvVar::secret("override".to_owned()))),
+                ("C".to_owned(), None),
+            ])),
+            preview: Some(BTreeMap::from([(
+                "D".to_owned(),
+                Some(EnvVar::plain("preview".to_owned())),
+            )])),
+        });
+
+        assert_eq!(
+            vars.production,
+            Some(BTreeMap::from([
+                ("A".to_owned(), Some(EnvVar::plain("base".to_owned()))),
+                ("B".to_owned(), Some(EnvVar::secret("override".to_owned()))),
+                ("C".to_owned(), None),
+            ]))
+        );
+        assert_eq!(
+            vars.preview,
+            Some(BTreeMap::from([(
+                "D".to_owned(),
+                Some(EnvVar::plain("preview".to_owned())),
+            )]))
+        );
+    }
+}
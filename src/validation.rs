@@ -0,0 +1,363 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use crate::{
+    diff::EnvVarsDiff,
+    types::{EnvVarsFile, Environment},
+};
+
+/// Largest value size in bytes accepted by Cloudflare Pages.
+pub const MAX_VALUE_SIZE: usize = 5 * 1024;
+
+/// Permission group allowing a token to read Pages projects.
+pub const PAGES_READ_PERMISSION: &str = "Pages Read";
+
+/// Permission group allowing a token to change Pages projects, including reading them.
+pub const PAGES_WRITE_PERMISSION: &str = "Pages Write";
+
+/// Largest number of variables Cloudflare Pages accepts in a single environment of a project.
+pub const MAX_VARS_PER_ENVIRONMENT: usize = 100;
+
+/// An environment that would end up with more variables than Cloudflare accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarLimitExceeded {
+    pub environment: Environment,
+    /// Number of variables before applying the changes.
+    pub current: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub limit: usize,
+}
+
+/// Checks whether a key is a portable environment variable name: ASCII letters, digits and
+/// underscores, not starting with a digit.
+pub fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Runs all offline checks against a file, returning a description of each problem found.
+/// `required_keys` must be present in every environment in the file.
+pub fn validate_env_vars(vars: &EnvVarsFile, required_keys: &[String]) -> Vec<String> {
+    let mut problems = vec![];
+
+    for (environment, env) in vars.environments() {
+        for (key, var) in env.iter() {
+            let var = match var {
+                Some(var) => var,
+                // Deletions are fine as long as the key is valid
+                None => {
+                    if !is_valid_key(key) {
+                        problems.push(format!("{environment}: invalid key name {key:?}"));
+                    }
+                    continue;
+                }
+            };
+            if !is_valid_key(key) {
+                problems.push(format!("{environment}: invalid key name {key:?}"));
+            }
+            if var.value.len() > MAX_VALUE_SIZE {
+                problems.push(format!(
+                    "{environment}: value of {key} is {} bytes, exceeding the limit of \
+                    {MAX_VALUE_SIZE} bytes",
+                    var.value.len()
+                ));
+            }
+        }
+
+        for collision in find_case_collisions(env) {
+            problems.push(format!(
+                "{environment}: keys differing only by case: {}",
+                collision.join(", ")
+            ));
+        }
+
+        let present_keys = env
+            .iter()
+            .filter(|(_, var)| var.is_some())
+            .map(|(key, _)| key);
+        let missing_keys = find_missing_keys(present_keys, required_keys);
+        if !missing_keys.is_empty() {
+            problems.push(format!(
+                "{environment}: missing required keys: {}",
+                missing_keys.join(", ")
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Finds environments whose variable count would exceed `limit` once `diff` is applied.
+pub fn find_var_limit_violations(diff: &EnvVarsDiff, limit: usize) -> Vec<VarLimitExceeded> {
+    let summary = diff.summary();
+    [
+        (
+            Environment::Production,
+            &diff.old_vars.production,
+            &summary.production,
+        ),
+        (
+            Environment::Preview,
+            &diff.old_vars.preview,
+            &summary.preview,
+        ),
+    ]
+    .into_iter()
+    .map(|(environment, old_env, changes)| VarLimitExceeded {
+        environment,
+        current: old_env.len(),
+        added: changes.added.len(),
+        removed: changes.removed.len(),
+        limit,
+    })
+    .filter(|violation| violation.total() > limit)
+    .collect()
+}
+
+/// Finds values larger than `max_bytes`, as `(environment, key, size in bytes)`.
+pub fn find_oversized_values(
+    vars: &EnvVarsFile,
+    max_bytes: usize,
+) -> Vec<(Environment, String, usize)> {
+    vars.environments()
+        .flat_map(|(environment, env)| {
+            env.iter().filter_map(move |(key, var)| {
+                var.as_ref()
+                    .map(|var| var.value.len())
+                    .filter(|size| *size > max_bytes)
+                    .map(|size| (environment, key.to_owned(), size))
+            })
+        })
+        .collect()
+}
+
+/// Returns the Pages permission groups missing from the permissions of a token.
+pub fn find_missing_pages_permissions(permissions: &[String]) -> Vec<&'static str> {
+    let has = |name: &str| permissions.iter().any(|permission| permission == name);
+
+    if has(PAGES_WRITE_PERMISSION) {
+        vec![]
+    } else if has(PAGES_READ_PERMISSION) {
+        vec![PAGES_WRITE_PERMISSION]
+    } else {
+        vec![PAGES_READ_PERMISSION, PAGES_WRITE_PERMISSION]
+    }
+}
+
+/// Returns the keys in `required_keys` that are absent from `keys`.
+pub fn find_missing_keys<'a, I>(keys: I, required_keys: &[String]) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let keys = keys.into_iter().collect::<BTreeSet<_>>();
+    required_keys
+        .iter()
+        .filter(|key| !keys.contains(key))
+        .cloned()
+        .collect()
+}
+
+/// Finds groups of keys that only differ by case, which is almost always a mistake since some
+/// platforms treat environment variable names case-insensitively.
+pub fn find_case_collisions<V>(env: &BTreeMap<String, V>) -> Vec<Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for key in env.keys() {
+        groups
+            .entry(key.to_uppercase())
+            .or_default()
+            .push(key.to_owned());
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+impl VarLimitExceeded {
+    /// Number of variables after applying the changes.
+    pub fn total(&self) -> usize {
+        self.current + self.added - self.removed
+    }
+}
+
+impl Display for VarLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} would have {} variables ({} existing, {} added, {} deleted), exceeding the limit \
+            of {}",
+            self.environment,
+            self.total(),
+            self.current,
+            self.added,
+            self.removed,
+            self.limit
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{patch::PatchMode, types::EnvVar, FullEnvVarsFile};
+
+    #[test]
+    fn test_find_case_collisions() {
+        let env: BTreeMap<_, _> = ["API_KEY", "Api_Key", "OTHER", "api_key", "URL"]
+            .into_iter()
+            .map(|key| (key.to_owned(), ()))
+            .collect();
+
+        assert_eq!(
+            find_case_collisions(&env),
+            vec![vec![
+                "API_KEY".to_owned(),
+                "Api_Key".to_owned(),
+                "api_key".to_owned()
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_find_case_collisions_none() {
+        let env: BTreeMap<_, _> = ["A", "B"]
+            .into_iter()
+            .map(|key| (key.to_owned(), ()))
+            .collect();
+
+        assert!(find_case_collisions(&env).is_empty());
+    }
+
+    #[test]
+    fn test_is_valid_key() {
+        assert!(is_valid_key("API_KEY"));
+        assert!(is_valid_key("_private2"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("2FA"));
+        assert!(!is_valid_key("with space"));
+        assert!(!is_valid_key("DASHED-KEY"));
+    }
+
+    #[test]
+    fn test_find_var_limit_violations() {
+        let old_vars = FullEnvVarsFile {
+            production: BTreeMap::from([
+                ("A".to_owned(), EnvVar::plain("1".to_owned())),
+                ("B".to_owned(), EnvVar::plain("2".to_owned())),
+            ]),
+            preview: BTreeMap::from([("A".to_owned(), EnvVar::plain("1".to_owned()))]),
+        };
+        let new_vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("A".to_owned(), None),
+                ("C".to_owned(), Some(EnvVar::plain("3".to_owned()))),
+                ("D".to_owned(), Some(EnvVar::plain("4".to_owned()))),
+            ])),
+            preview: Some(BTreeMap::from([(
+                "B".to_owned(),
+                Some(EnvVar::plain("2".to_owned())),
+            )])),
+        };
+        let diff = EnvVarsDiff::new(old_vars, &new_vars, PatchMode::Merge);
+
+        let violations = find_var_limit_violations(&diff, 2);
+
+        assert_eq!(
+            violations,
+            vec![VarLimitExceeded {
+                environment: Environment::Production,
+                current: 2,
+                added: 2,
+                removed: 1,
+                limit: 2,
+            }]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "production would have 3 variables (2 existing, 2 added, 1 deleted), exceeding the \
+            limit of 2"
+        );
+        assert!(find_var_limit_violations(&diff, 3).is_empty());
+    }
+
+    #[test]
+    fn test_find_missing_pages_permissions() {
+        let permissions =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+        assert!(find_missing_pages_permissions(&permissions(&["Pages Write"])).is_empty());
+        assert_eq!(
+            find_missing_pages_permissions(&permissions(&["DNS Read", "Pages Read"])),
+            vec!["Pages Write"]
+        );
+        assert_eq!(
+            find_missing_pages_permissions(&permissions(&[])),
+            vec!["Pages Read", "Pages Write"]
+        );
+    }
+
+    #[test]
+    fn test_find_oversized_values() {
+        let vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("SMALL".to_owned(), Some(EnvVar::plain("x".repeat(10)))),
+                ("LARGE".to_owned(), Some(EnvVar::secret("x".repeat(11)))),
+                ("DELETED".to_owned(), None),
+            ])),
+            preview: Some(BTreeMap::from([(
+                "LARGE".to_owned(),
+                Some(EnvVar::plain("é".repeat(6))),
+            )])),
+        };
+
+        assert_eq!(
+            find_oversized_values(&vars, 10),
+            vec![
+                (Environment::Production, "LARGE".to_owned(), 11),
+                (Environment::Preview, "LARGE".to_owned(), 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_env_vars() {
+        let vars = EnvVarsFile {
+            production: Some(BTreeMap::from([
+                ("API_KEY".to_owned(), Some(EnvVar::plain("1".to_owned()))),
+                ("api_key".to_owned(), Some(EnvVar::plain("2".to_owned()))),
+                ("BAD KEY".to_owned(), Some(EnvVar::plain("3".to_owned()))),
+                (
+                    "BIG".to_owned(),
+                    Some(EnvVar::plain("x".repeat(MAX_VALUE_SIZE + 1))),
+                ),
+            ])),
+            preview: Some(BTreeMap::from([
+                (
+                    "DATABASE_URL".to_owned(),
+                    Some(EnvVar::plain("postgres://".to_owned())),
+                ),
+                ("REMOVED".to_owned(), None),
+            ])),
+        };
+
+        assert_eq!(
+            validate_env_vars(&vars, &["DATABASE_URL".to_owned()]),
+            vec![
+                "production: invalid key name \"BAD KEY\"".to_owned(),
+                "production: value of BIG is 5121 bytes, exceeding the limit of 5120 bytes"
+                    .to_owned(),
+                "production: keys differing only by case: API_KEY, api_key".to_owned(),
+                "production: missing required keys: DATABASE_URL".to_owned(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use toml::{Table, Value};
+
+/// Default location of the Wrangler config file, relative to the working directory.
+pub const DEFAULT_WRANGLER_PATH: &str = "wrangler.toml";
+
+/// Extracts the project name from the content of a `wrangler.toml` file.
+pub fn project_name_from_wrangler(content: &str) -> Result<String> {
+    let table: Table = toml::from_str(content)?;
+    match table.get("name") {
+        Some(Value::String(name)) if !name.is_empty() => Ok(name.to_owned()),
+        Some(_) => anyhow::bail!("the name field must be a non-empty string"),
+        None => anyhow::bail!("missing name field"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_name_from_wrangler() {
+        let content = r#"
+name = "my-project"
+pages_build_output_dir = "./dist"
+
+[vars]
+name = "other"
+"#;
+
+        assert_eq!(project_name_from_wrangler(content).unwrap(), "my-project");
+        assert!(project_name_from_wrangler("[vars]\nname = \"other\"").is_err());
+        assert!(project_name_from_wrangler("name = 1").is_err());
+        assert!(project_name_from_wrangler("name = ").is_err());
+    }
+}